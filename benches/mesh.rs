@@ -1,10 +1,13 @@
 use std::collections::BTreeSet;
 
 use binary_greedy_meshing as bgm;
-use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
 const SIZE: usize = 16;
 const SIZE2: usize = SIZE.pow(2);
 const CS: usize = 62;
+const SMALL_CS: usize = 30;
+const SMALL_SIZE2: usize = (SMALL_CS / 4).pow(2);
 
 fn voxel_buffer<F>(f: F) -> Box<[u16; bgm::Mesher::<CS>::CS_P3]>
 where
@@ -41,10 +44,33 @@ fn transparent_sphere(x: usize, y: usize, z: usize) -> u16 {
     }
 }
 
+fn small_voxel_buffer() -> Box<[u16]> {
+    let mut voxels = vec![0; bgm::Mesher::<SMALL_CS>::CS_P3].into_boxed_slice();
+    for x in 0..SMALL_CS {
+        for y in 0..SMALL_CS {
+            for z in 0..SMALL_CS {
+                voxels[bgm::pad_linearize::<SMALL_CS>(x, y, z)] = small_opaque_sphere(x, y, z);
+            }
+        }
+    }
+    voxels
+}
+
+fn small_opaque_sphere(x: usize, y: usize, z: usize) -> u16 {
+    let center = (SMALL_CS / 2) as i32;
+    if (x as i32 - center).pow(2) + (y as i32 - center).pow(2) + (z as i32 - center).pow(2)
+        < SMALL_SIZE2 as i32
+    {
+        1
+    } else {
+        0
+    }
+}
+
 fn fast_mesh_opaque(c: &mut Criterion) {
     let voxels = voxel_buffer(opaque_sphere);
     let mut mesher = bgm::Mesher::<CS>::new();
-    let opaque_mask = bgm::compute_opaque_mask::<CS>(voxels.as_slice(), &BTreeSet::new());
+    let opaque_mask = bgm::compute_opaque_mask::<CS, _>(voxels.as_slice(), &BTreeSet::new());
     let trans_mask = vec![0; bgm::Mesher::<CS>::CS_P2].into_boxed_slice();
     c.bench_function("fast_mesh_opaque", |b| {
         b.iter(|| {
@@ -58,6 +84,26 @@ fn fast_mesh_opaque(c: &mut Criterion) {
     });
 }
 
+/// Remeshing the layers around a single edited voxel
+fn fast_remesh_voxel(c: &mut Criterion) {
+    let voxels = voxel_buffer(opaque_sphere);
+    let mut mesher = bgm::Mesher::<CS>::new();
+    let opaque_mask = bgm::compute_opaque_mask::<CS, _>(voxels.as_slice(), &BTreeSet::new());
+    let trans_mask = vec![0; bgm::Mesher::<CS>::CS_P2].into_boxed_slice();
+    mesher.fast_mesh(voxels.as_slice(), &opaque_mask, &trans_mask);
+    c.bench_function("fast_remesh_voxel", |b| {
+        b.iter(|| {
+            mesher.fast_remesh(
+                black_box(voxels.as_slice()),
+                black_box(&opaque_mask),
+                black_box(&trans_mask),
+                [31, 31, 31],
+                [32, 32, 32],
+            );
+        })
+    });
+}
+
 fn mesh_opaque(c: &mut Criterion) {
     let voxels = voxel_buffer(opaque_sphere);
     let mut mesher = bgm::Mesher::<CS>::new();
@@ -74,8 +120,8 @@ fn fast_mesh_transparent(c: &mut Criterion) {
     let voxels = voxel_buffer(transparent_sphere);
     let mut mesher = bgm::Mesher::<CS>::new();
     let transparent_blocks = BTreeSet::from([2]);
-    let opaque_mask = bgm::compute_opaque_mask::<CS>(voxels.as_slice(), &BTreeSet::new());
-    let trans_mask = bgm::compute_transparent_mask::<CS>(voxels.as_slice(), &transparent_blocks);
+    let opaque_mask = bgm::compute_opaque_mask::<CS, _>(voxels.as_slice(), &BTreeSet::new());
+    let trans_mask = bgm::compute_transparent_mask::<CS, _>(voxels.as_slice(), &transparent_blocks);
     c.bench_function("fast_mesh_transparent", |b| {
         b.iter(|| {
             mesher.clear();
@@ -100,11 +146,129 @@ fn mesh_transparent(c: &mut Criterion) {
     });
 }
 
+fn mesh_transparent_set(c: &mut Criterion) {
+    let voxels = voxel_buffer(transparent_sphere);
+    let mut mesher = bgm::Mesher::<CS>::new();
+    let transparent_blocks = bgm::TransparentSet::from([2]);
+    c.bench_function("mesh_transparent_set", |b| {
+        b.iter(|| {
+            mesher.clear();
+            mesher.mesh(black_box(voxels.as_slice()), black_box(&transparent_blocks));
+        })
+    });
+}
+
+/// Same as mesh_transparent_set, from an unpadded chunk with air around it
+fn mesh_unpadded(c: &mut Criterion) {
+    let mut voxels = Vec::with_capacity(CS * CS * CS);
+    for y in 0..CS {
+        for x in 0..CS {
+            for z in 0..CS {
+                voxels.push(transparent_sphere(x, y, z));
+            }
+        }
+    }
+    let air = vec![0; CS * CS];
+    let mut mesher = bgm::Mesher::<CS>::new();
+    let transparent_blocks = bgm::TransparentSet::from([2]);
+    c.bench_function("mesh_unpadded", |b| {
+        b.iter(|| {
+            mesher.clear();
+            mesher.mesh_unpadded(
+                black_box(&voxels),
+                [&air, &air, &air, &air, &air, &air],
+                black_box(&transparent_blocks),
+            );
+        })
+    });
+}
+
+/// Same chunk as mesh_unpadded, copied into a padded buffer to compute its masks and use fast_mesh
+fn copy_fast_mesh(c: &mut Criterion) {
+    let mut voxels = Vec::with_capacity(CS * CS * CS);
+    for y in 0..CS {
+        for x in 0..CS {
+            for z in 0..CS {
+                voxels.push(transparent_sphere(x, y, z));
+            }
+        }
+    }
+    let mut padded = vec![0; bgm::Mesher::<CS>::CS_P3];
+    let mut mesher = bgm::Mesher::<CS>::new();
+    let transparent_blocks = bgm::TransparentSet::from([2]);
+    c.bench_function("copy_fast_mesh", |b| {
+        b.iter(|| {
+            for y in 0..CS {
+                for x in 0..CS {
+                    for z in 0..CS {
+                        padded[bgm::pad_linearize::<CS>(x, y, z)] =
+                            voxels[bgm::Mesher::<CS>::linearize(x, y, z)];
+                    }
+                }
+            }
+            let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&padded, &transparent_blocks);
+            let trans_mask = bgm::compute_transparent_mask::<CS, _>(&padded, &transparent_blocks);
+            mesher.clear();
+            mesher.fast_mesh(
+                black_box(&padded),
+                black_box(&opaque_mask),
+                black_box(&trans_mask),
+            );
+        })
+    });
+}
+
+/// Small chunks use u32 masks by default
+fn fast_mesh_small(c: &mut Criterion) {
+    let voxels = small_voxel_buffer();
+    let mut mesher = bgm::Mesher::<SMALL_CS>::new();
+    let opaque_mask = bgm::compute_opaque_mask::<SMALL_CS, _>(&voxels, &BTreeSet::new());
+    let trans_mask = vec![0; bgm::Mesher::<SMALL_CS>::CS_P2].into_boxed_slice();
+    c.bench_function("fast_mesh_small", |b| {
+        b.iter(|| {
+            mesher.clear();
+            mesher.fast_mesh(
+                black_box(&voxels),
+                black_box(&opaque_mask),
+                black_box(&trans_mask),
+            );
+        })
+    });
+}
+
+/// Same as fast_mesh_small but forcing u64 masks, for comparison
+fn fast_mesh_small_u64(c: &mut Criterion) {
+    let voxels = small_voxel_buffer();
+    let mut mesher = bgm::Mesher::<SMALL_CS, u64>::new();
+    let opaque_mask: Box<[u64]> =
+        bgm::compute_opaque_mask::<SMALL_CS, _>(&voxels, &BTreeSet::new())
+            .iter()
+            .map(|&col| col as u64)
+            .collect();
+    let trans_mask = vec![0; bgm::Mesher::<SMALL_CS>::CS_P2].into_boxed_slice();
+    c.bench_function("fast_mesh_small_u64", |b| {
+        b.iter(|| {
+            mesher.clear();
+            mesher.fast_mesh(
+                black_box(&voxels),
+                black_box(&opaque_mask),
+                black_box(&trans_mask),
+            );
+        })
+    });
+}
+
 criterion_group!(
     mesh,
     fast_mesh_opaque,
+    fast_remesh_voxel,
     mesh_opaque,
     fast_mesh_transparent,
-    mesh_transparent
+    mesh_transparent,
+    mesh_transparent_set,
+    mesh_unpadded,
+    copy_fast_mesh,
+    fast_mesh_small,
+    fast_mesh_small_u64
 );
 criterion_main!(mesh);
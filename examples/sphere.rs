@@ -80,7 +80,7 @@ fn setup(
 fn generate_mesh() -> Mesh {
     let voxels = voxel_buffer();
     let mut mesher = bgm::Mesher::<CS>::new();
-    let opaque_mask = bgm::compute_opaque_mask::<CS>(&voxels, &BTreeSet::new());
+    let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&voxels, &BTreeSet::new());
     let trans_mask = vec![0; bgm::Mesher::<CS>::CS_P2].into_boxed_slice();
     mesher.fast_mesh(&voxels, &opaque_mask, &trans_mask);
     let mut positions = Vec::new();
@@ -93,7 +93,7 @@ fn generate_mesh() -> Mesh {
             for vertex in vertices_packed.iter() {
                 let [x, y, z] = vertex.xyz();
                 positions.push([x as f32, y as f32, z as f32]);
-                normals.push(n.clone());
+                normals.push(n);
             }
         }
     }
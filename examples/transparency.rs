@@ -117,43 +117,30 @@ fn generate_meshes() -> [Mesh; 3] {
     let mut transparent_blocks = BTreeSet::new();
     transparent_blocks.insert(2);
     transparent_blocks.insert(3);
-    let opaque_mask = bgm::compute_opaque_mask::<CS>(&voxels, &transparent_blocks);
-    let trans_mask = bgm::compute_transparent_mask::<CS>(&voxels, &transparent_blocks);
+    let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&voxels, &transparent_blocks);
+    let trans_mask = bgm::compute_transparent_mask::<CS, _>(&voxels, &transparent_blocks);
     mesher.fast_mesh(&voxels, &opaque_mask, &trans_mask);
-    let mut positions: [_; 3] = core::array::from_fn(|_| Vec::new());
-    let mut normals: [_; 3] = core::array::from_fn(|_| Vec::new());
-    let mut indices: [_; 3] = core::array::from_fn(|_| Vec::new());
-    for (face_n, quads) in mesher.quads.iter().enumerate() {
-        let face: bgm::Face = (face_n as u8).into();
-        let n = face.n().map(|v| v as f32);
-        for &quad in quads {
-            let voxel_i = quad.voxel_id() as usize - 1;
-            let vertices_packed = face.vertices_packed(quad);
-            for &vertex in vertices_packed.iter() {
-                let [x, y, z] = vertex.xyz();
-                positions[voxel_i].push([x as f32, y as f32, z as f32]);
-                normals[voxel_i].push(n.clone());
-            }
-        }
-    }
-    for i in 0..positions.len() {
-        indices[i] = bgm::indices(positions[i].len() / 4);
+    let mut buffers: [_; 3] = core::array::from_fn(|_| bgm::MeshBuffers::new());
+    // Each voxel type's quads are contiguous in the groups
+    let groups = bgm::QuadGroups::new(&mesher.quads, |quad| quad.voxel_id() as usize - 1);
+    for (&voxel_i, face, quads) in groups.iter() {
+        buffers[voxel_i].push_quads(face, quads, [0.; 3], 1.);
     }
     core::array::from_fn(|i| {
         let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
         mesh.insert_attribute(
             Mesh::ATTRIBUTE_POSITION,
-            VertexAttributeValues::Float32x3(positions[i].clone()),
+            VertexAttributeValues::Float32x3(buffers[i].positions.clone()),
         );
         mesh.insert_attribute(
             Mesh::ATTRIBUTE_NORMAL,
-            VertexAttributeValues::Float32x3(normals[i].clone()),
+            VertexAttributeValues::Float32x3(buffers[i].normals.clone()),
         );
         mesh.insert_attribute(
             Mesh::ATTRIBUTE_UV_0,
-            VertexAttributeValues::Float32x2(vec![[0.0; 2]; positions[i].len()]),
+            VertexAttributeValues::Float32x2(buffers[i].uvs.clone()),
         );
-        mesh.insert_indices(Indices::U32(indices[i].clone()));
+        mesh.insert_indices(Indices::U32(buffers[i].indices.clone()));
         mesh
     })
 }
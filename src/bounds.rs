@@ -0,0 +1,77 @@
+use crate::Face;
+
+/// An axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    /// The empty box, which contains nothing and is the identity of [`Aabb::union`]
+    pub const EMPTY: Self = Self {
+        min: [f32::INFINITY; 3],
+        max: [f32::NEG_INFINITY; 3],
+    };
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[i] > self.max[i])
+    }
+
+    /// The smallest box containing both boxes
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min: core::array::from_fn(|i| self.min[i].min(other.min[i])),
+            max: core::array::from_fn(|i| self.max[i].max(other.max[i])),
+        }
+    }
+
+    /// The box between integer corners in chunk space, empty if min > max
+    pub(crate) fn from_extents((min, max): ([u32; 3], [u32; 3])) -> Self {
+        if (0..3).any(|i| min[i] > max[i]) {
+            return Self::EMPTY;
+        }
+        Self {
+            min: min.map(|c| c as f32),
+            max: max.map(|c| c as f32),
+        }
+    }
+
+    /// Converts a box in chunk space to world space, for a chunk at `origin` with voxels of size `scale`
+    pub fn to_world(self, origin: [f32; 3], scale: f32) -> Self {
+        Self {
+            min: core::array::from_fn(|i| origin[i] + self.min[i] * scale),
+            max: core::array::from_fn(|i| origin[i] + self.max[i] * scale),
+        }
+    }
+
+    /// Which face groups (indexed by [`Face`]) of the quads in this box can be seen from `camera`,
+    /// faces looking away from the camera are never visible
+    pub fn visible_faces(&self, camera: [f32; 3]) -> [bool; 6] {
+        core::array::from_fn(|face| Face::from(face as u8).is_visible(self, camera))
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl Face {
+    /// Whether some quads of this face inside `aabb` can be seen from `camera`,
+    /// which is the case if the camera is on the side the face is looking at of some point of the box
+    pub fn is_visible(&self, aabb: &Aabb, camera: [f32; 3]) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+        match self {
+            Face::Up => camera[1] > aabb.min[1],
+            Face::Down => camera[1] < aabb.max[1],
+            Face::Right => camera[0] > aabb.min[0],
+            Face::Left => camera[0] < aabb.max[0],
+            Face::Front => camera[2] > aabb.min[2],
+            Face::Back => camera[2] < aabb.max[2],
+        }
+    }
+}
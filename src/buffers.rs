@@ -0,0 +1,123 @@
+use crate::{Face, Quad, quad_indices};
+use alloc::vec::Vec;
+
+/// Float vertex buffers built from quads, for renderers that can't draw the quads with instancing.
+/// Each quad gets 4 vertices (in the order of [`Face::vertices_packed`]) and 6 indices (as [`crate::indices`]),
+/// positions are placed in world space with the chunk's `origin` and the size of a voxel `scale`:
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut voxels = vec![0u16; bgm::Mesher::<16>::CS_P3];
+/// voxels[bgm::pad_linearize::<16>(0, 0, 0)] = 1;
+/// let mut mesher = bgm::Mesher::<16>::new();
+/// mesher.mesh(&voxels, &bgm::TransparentSet::new());
+/// let buffers = bgm::MeshBuffers::from_quads(&mesher.quads, [16., 0., 0.], 0.5);
+/// assert_eq!((buffers.positions.len(), buffers.indices.len()), (24, 36));
+/// assert!(buffers.positions.contains(&[16.5, 0.5, 0.5]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshBuffers {
+    /// The position of each vertex: `origin + chunk position * scale`
+    pub positions: Vec<[f32; 3]>,
+    /// The normal of each vertex, the [`Face::n`] of its quad
+    pub normals: Vec<[f32; 3]>,
+    /// The uv of each vertex in voxels, so that textures repeat along merged quads
+    pub uvs: Vec<[f32; 2]>,
+    /// The [`Quad::voxel_id`] of each vertex's quad
+    pub voxel_ids: Vec<u32>,
+    pub indices: Vec<u32>,
+}
+
+/// A vertex of [`MeshBuffers::interleaved`], laid out as 9 f32 followed by the voxel id
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    pub voxel_id: u32,
+}
+
+impl MeshVertex {
+    /// The 4 vertices of a quad, in the order of [`Face::vertices_packed`] (without its size limit)
+    pub fn quad<const CX: usize, const CY: usize, const CZ: usize>(
+        face: Face,
+        quad: Quad<CX, CY, CZ>,
+        origin: [f32; 3],
+        scale: f32,
+    ) -> [Self; 4] {
+        let normal = face.n().map(|c| c as f32);
+        let voxel_id = quad.voxel_id() as u32;
+        face.quad_corners(quad).map(|(position, uv)| Self {
+            position: core::array::from_fn(|i| origin[i] + position[i] as f32 * scale),
+            normal,
+            uv: uv.map(|c| c as f32),
+            voxel_id,
+        })
+    }
+}
+
+impl MeshBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the buffers of the quads of each face (such as [`crate::ChunkMesher::quads`])
+    pub fn from_quads<const CX: usize, const CY: usize, const CZ: usize>(
+        quads: &[Vec<Quad<CX, CY, CZ>>; 6],
+        origin: [f32; 3],
+        scale: f32,
+    ) -> Self {
+        let mut buffers = Self::new();
+        for (face, quads) in quads.iter().enumerate() {
+            buffers.push_quads(Face::from(face as u8), quads, origin, scale);
+        }
+        buffers
+    }
+
+    /// Appends the vertices and indices of quads of the same face
+    pub fn push_quads<const CX: usize, const CY: usize, const CZ: usize>(
+        &mut self,
+        face: Face,
+        quads: &[Quad<CX, CY, CZ>],
+        origin: [f32; 3],
+        scale: f32,
+    ) {
+        let vertices = 4 * quads.len();
+        self.positions.reserve(vertices);
+        self.normals.reserve(vertices);
+        self.uvs.reserve(vertices);
+        self.voxel_ids.reserve(vertices);
+        self.indices.reserve(6 * quads.len());
+        for &quad in quads {
+            let first_quad = (self.positions.len() / 4) as u32;
+            for vertex in MeshVertex::quad(face, quad, origin, scale) {
+                self.positions.push(vertex.position);
+                self.normals.push(vertex.normal);
+                self.uvs.push(vertex.uv);
+                self.voxel_ids.push(vertex.voxel_id);
+            }
+            self.indices.extend(quad_indices(first_quad));
+        }
+    }
+
+    /// Call this before reusing the buffers for another mesh, to keep their allocations
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.normals.clear();
+        self.uvs.clear();
+        self.voxel_ids.clear();
+        self.indices.clear();
+    }
+
+    /// The vertices in a single buffer, to use with `indices`
+    pub fn interleaved(&self) -> Vec<MeshVertex> {
+        (0..self.positions.len())
+            .map(|i| MeshVertex {
+                position: self.positions[i],
+                normal: self.normals[i],
+                uv: self.uvs[i],
+                voxel_id: self.voxel_ids[i],
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{ChunkMesher, Layout, Mask, MaskOf, Voxel, VoxelClassifier, VoxelKind, Zxy};
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+
+/// How [`ChunkMasks::from_neighbours`] fills the padding of missing neighbours
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MissingNeighbour<V> {
+    /// Faces on that side are visible
+    Air,
+    /// Filled with the given voxel, an opaque one hides the faces on that side
+    Solid(V),
+}
+
+/// A padded voxel buffer along with its opaque and transparent masks, kept in sync on every edit
+/// so they never have to be rebuilt with `compute_opaque_mask`/`compute_transparent_mask`.
+/// `C` decides which voxels are opaque or transparent, the dimensions, mask type and layout are the same as [`ChunkMesher`]'s.
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut chunk = bgm::ChunkMasks::<u16, _, 62>::new(bgm::TransparentSet::from([2]));
+/// chunk.set(0, 0, 0, 1);
+/// chunk.fill_region([0, 1, 0], [62, 2, 62], 2);
+/// let mut mesher = bgm::Mesher::<62>::new();
+/// mesher.fast_mesh(chunk.voxels(), chunk.opaque_mask(), chunk.trans_mask());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChunkMasks<
+    V: Voxel,
+    C: VoxelClassifier<V>,
+    const CX: usize,
+    const CY: usize = CX,
+    const CZ: usize = CX,
+    M: Mask = MaskOf<CZ>,
+    L: Layout = Zxy,
+> {
+    voxels: Box<[V]>,
+    opaque_mask: Box<[M]>,
+    trans_mask: Box<[M]>,
+    classifier: C,
+    layout: PhantomData<L>,
+}
+
+impl<
+    V: Voxel,
+    C: VoxelClassifier<V>,
+    const CX: usize,
+    const CY: usize,
+    const CZ: usize,
+    M: Mask,
+    L: Layout,
+> ChunkMasks<V, C, CX, CY, CZ, M, L>
+{
+    const CX_P: usize = CX + 2;
+    const CY_P: usize = CY + 2;
+    const CZ_P: usize = CZ + 2;
+
+    /// Creates a chunk filled with air (padding included)
+    pub fn new(classifier: C) -> Self {
+        Self::assert_dims();
+        Self {
+            voxels: vec![V::AIR; ChunkMesher::<CX, CY, CZ, M, L>::VOXELS_P].into_boxed_slice(),
+            opaque_mask: vec![M::ZERO; ChunkMesher::<CX, CY, CZ, M, L>::COLUMNS_P]
+                .into_boxed_slice(),
+            trans_mask: vec![M::ZERO; ChunkMesher::<CX, CY, CZ, M, L>::COLUMNS_P]
+                .into_boxed_slice(),
+            classifier,
+            layout: PhantomData,
+        }
+    }
+
+    /// Takes an existing padded voxel buffer and computes its masks
+    pub fn from_voxels(voxels: impl Into<Box<[V]>>, classifier: C) -> Self {
+        Self::assert_dims();
+        let voxels = voxels.into();
+        assert_eq!(voxels.len(), ChunkMesher::<CX, CY, CZ, M, L>::VOXELS_P);
+        let opaque_mask =
+            ChunkMesher::<CX, CY, CZ, M, L>::compute_opaque_mask(&voxels, &classifier);
+        let trans_mask =
+            ChunkMesher::<CX, CY, CZ, M, L>::compute_transparent_mask(&voxels, &classifier);
+        Self {
+            voxels,
+            opaque_mask,
+            trans_mask,
+            classifier,
+            layout: PhantomData,
+        }
+    }
+
+    /// Builds the padded voxels and their masks from an unpadded chunk of CX*CY*CZ voxels
+    /// (see [`ChunkMesher::linearize`]) and its neighbours' borders.
+    ///
+    /// `neighbour` is called with the offset of a neighbouring chunk (each axis in -1..=1)
+    /// and a position inside it, it returns None if that chunk is missing,
+    /// in which case the padding is filled according to `missing`.
+    pub fn from_neighbours(
+        center: &[V],
+        neighbour: impl Fn([i32; 3], [usize; 3]) -> Option<V>,
+        missing: MissingNeighbour<V>,
+        classifier: C,
+    ) -> Self {
+        assert_eq!(center.len(), CX * CY * CZ);
+        let missing = match missing {
+            MissingNeighbour::Air => V::AIR,
+            MissingNeighbour::Solid(voxel) => voxel,
+        };
+        // Offset of the chunk and position inside it of a padded coordinate on an axis of size `c`
+        let split = |p: usize, c: usize| -> (i32, usize) {
+            if p == 0 {
+                (-1, c - 1)
+            } else if p == c + 1 {
+                (1, 0)
+            } else {
+                (0, p - 1)
+            }
+        };
+        let mut voxels = vec![V::AIR; ChunkMesher::<CX, CY, CZ, M, L>::VOXELS_P].into_boxed_slice();
+        for py in 0..Self::CY_P {
+            let (oy, y) = split(py, CY);
+            for px in 0..Self::CX_P {
+                let (ox, x) = split(px, CX);
+                for pz in 0..Self::CZ_P {
+                    let (oz, z) = split(pz, CZ);
+                    voxels[Self::padded_index(px, py, pz)] = if [ox, oy, oz] == [0, 0, 0] {
+                        center[ChunkMesher::<CX, CY, CZ, M, L>::linearize(x, y, z)]
+                    } else {
+                        neighbour([ox, oy, oz], [x, y, z]).unwrap_or(missing)
+                    };
+                }
+            }
+        }
+        Self::from_voxels(voxels, classifier)
+    }
+
+    fn assert_dims() {
+        ChunkMesher::<CX, CY, CZ, M, L>::assert_dims();
+    }
+
+    /// The padded voxel buffer
+    pub fn voxels(&self) -> &[V] {
+        &self.voxels
+    }
+
+    pub fn opaque_mask(&self) -> &[M] {
+        &self.opaque_mask
+    }
+
+    pub fn trans_mask(&self) -> &[M] {
+        &self.trans_mask
+    }
+
+    pub fn classifier(&self) -> &C {
+        &self.classifier
+    }
+
+    /// Gets the voxel at x;y;z (in 0..CX;0..CY;0..CZ), panics out of the chunk
+    pub fn get(&self, x: usize, y: usize, z: usize) -> V {
+        Self::assert_in_chunk(x, y, z);
+        self.get_padded(x + 1, y + 1, z + 1)
+    }
+
+    /// Sets the voxel at x;y;z (in 0..CX;0..CY;0..CZ) and updates its bit in the masks, panics out of the chunk
+    pub fn set(&mut self, x: usize, y: usize, z: usize, voxel: V) {
+        Self::assert_in_chunk(x, y, z);
+        self.set_padded(x + 1, y + 1, z + 1, voxel);
+    }
+
+    /// An unpadded position out of the chunk would land in the padding (or the next column) and flip its mask bits
+    #[inline]
+    fn assert_in_chunk(x: usize, y: usize, z: usize) {
+        assert!(x < CX && y < CY && z < CZ, "position out of the chunk");
+    }
+
+    /// Gets the voxel at x;y;z in padded coordinates (in 0..CX_P;0..CY_P;0..CZ_P), 0 and CX_P-1 being the padding
+    pub fn get_padded(&self, x: usize, y: usize, z: usize) -> V {
+        self.voxels[Self::padded_index(x, y, z)]
+    }
+
+    /// Sets the voxel at x;y;z in padded coordinates (in 0..CX_P;0..CY_P;0..CZ_P), 0 and CX_P-1 being the padding,
+    /// this is how neighbouring chunks' borders are updated
+    pub fn set_padded(&mut self, x: usize, y: usize, z: usize, voxel: V) {
+        let column = x + y * Self::CX_P;
+        self.voxels[Self::padded_index(x, y, z)] = voxel;
+        let bit = M::ONE << z;
+        self.opaque_mask[column] &= !bit;
+        self.trans_mask[column] &= !bit;
+        match self.classifier.classify(voxel) {
+            VoxelKind::Opaque => self.opaque_mask[column] |= bit,
+            VoxelKind::Transparent => self.trans_mask[column] |= bit,
+            VoxelKind::Air => {}
+        }
+    }
+
+    /// Sets every voxel with min <= x;y;z < max (in 0..CX;0..CY;0..CZ) to `voxel`,
+    /// the masks are updated a column at a time
+    pub fn fill_region(&mut self, min: [usize; 3], max: [usize; 3], voxel: V) {
+        let [x0, y0, z0] = min;
+        let [x1, y1, z1] = max;
+        assert!(x1 <= CX && y1 <= CY && z1 <= CZ, "region out of the chunk");
+        if x0 >= x1 || y0 >= y1 || z0 >= z1 {
+            return;
+        }
+        // Bits z0+1..z1+1 of a padded column
+        let run = (!M::ZERO >> (M::BITS - (z1 - z0))) << (z0 + 1);
+        let kind = self.classifier.classify(voxel);
+        for y in (y0 + 1)..(y1 + 1) {
+            for x in (x0 + 1)..(x1 + 1) {
+                let column = x + y * Self::CX_P;
+                for z in (z0 + 1)..(z1 + 1) {
+                    self.voxels[Self::padded_index(x, y, z)] = voxel;
+                }
+                self.opaque_mask[column] &= !run;
+                self.trans_mask[column] &= !run;
+                match kind {
+                    VoxelKind::Opaque => self.opaque_mask[column] |= run,
+                    VoxelKind::Transparent => self.trans_mask[column] |= run,
+                    VoxelKind::Air => {}
+                }
+            }
+        }
+    }
+
+    /// Sets many voxels at once, positions are x;y;z in 0..CX;0..CY;0..CZ
+    pub fn set_many(&mut self, voxels: impl IntoIterator<Item = ([usize; 3], V)>) {
+        for ([x, y, z], voxel) in voxels {
+            self.set(x, y, z, voxel);
+        }
+    }
+
+    #[inline]
+    fn padded_index(x: usize, y: usize, z: usize) -> usize {
+        assert!(
+            x < Self::CX_P && y < Self::CY_P && z < Self::CZ_P,
+            "position out of the padded chunk"
+        );
+        ChunkMesher::<CX, CY, CZ, M, L>::padded_index(x, y, z)
+    }
+}
@@ -1,6 +1,4 @@
-use crate::Quad;
-
-const MASK_XYZ: u64 = 0b111111_111111_111111;
+use crate::{Layout, Quad, Zxy, layout};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Face {
@@ -61,11 +59,22 @@ impl Vertex {
         (self.0 >> 24) & Vertex::MASK_6
     }
 
+    /// Ambient occlusion of the vertex, from 0 (unoccluded) to 3 (fully occluded)
+    pub fn ao(&self) -> u32 {
+        self.0 >> 30
+    }
+
     pub fn xyz(&self) -> [u32; 3] {
         [self.x(), self.y(), self.z()]
     }
 }
 
+impl Default for Vertex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 fn packed_xyz(x: u32, y: u32, z: u32) -> u32 {
     (z << 12) | (y << 6) | x
 }
@@ -93,49 +102,161 @@ impl Face {
         }
     }
 
+    /// The min and max corners of a quad, in chunk space (as the vertices of [`Face::vertices_packed`])
+    pub(crate) fn quad_extents<const CX: usize, const CY: usize, const CZ: usize>(
+        &self,
+        quad: Quad<CX, CY, CZ>,
+    ) -> ([u32; 3], [u32; 3]) {
+        let [x, y, z] = quad.xyz().map(|c| c as u32);
+        let w = quad.width() as u32;
+        let h = quad.height() as u32;
+        match self {
+            Face::Up => ([x, y, z], [x + w, y, z + h]),
+            Face::Down => ([x - w, y, z], [x, y, z + h]),
+            Face::Right => ([x, y - w, z], [x, y, z + h]),
+            Face::Left => ([x, y, z], [x, y + w, z + h]),
+            Face::Front => ([x - w, y, z], [x, y + h, z]),
+            Face::Back => ([x, y, z], [x + w, y + h, z]),
+        }
+    }
+
     /// Takes a quad as outputted by binary greedy meshing, and outputs 4 vertices encoded as:
-    /// (v << 24) | (u << 18) | (z << 12) | (y << 6) | x
-    pub fn vertices_packed(&self, quad: Quad) -> [Vertex; 4] {
+    /// (ao << 30) | (v << 24) | (u << 18) | (z << 12) | (y << 6) | x
+    ///
+    /// ao is 0 unless the quad was meshed with AO (see [`Quad::ao`]).
+    ///
+    /// Vertex fields are 6 bits so this only accepts quads from chunks with every dimension <= 63:
+    /// ```compile_fail
+    /// use binary_greedy_meshing as bgm;
+    /// let vertices = bgm::Face::Up.vertices_packed(bgm::Quad::<126>(0));
+    /// ```
+    /// ```compile_fail
+    /// use binary_greedy_meshing as bgm;
+    /// let vertices = bgm::Face::Up.vertices_packed(bgm::Quad::<32, 256, 32>(0));
+    /// ```
+    pub fn vertices_packed<const CX: usize, const CY: usize, const CZ: usize>(
+        &self,
+        quad: Quad<CX, CY, CZ>,
+    ) -> [Vertex; 4] {
+        const {
+            assert!(
+                CX < 64 && CY < 64 && CZ < 64,
+                "packed vertices only fit chunks with dimensions <= 63"
+            );
+        }
+        let ao = quad.ao();
+        let corners = self.quad_corners(quad);
+        core::array::from_fn(|i| {
+            let ([x, y, z], [u, v]) = corners[i];
+            Vertex(Vertex::pack(packed_xyz(x, y, z), u, v).0 | (ao[i] as u32) << 30)
+        })
+    }
+
+    /// The 4 vertices of a quad as (position in chunk space, uv), in the order of [`Face::vertices_packed`],
+    /// without its size limit
+    pub(crate) fn quad_corners<const CX: usize, const CY: usize, const CZ: usize>(
+        &self,
+        quad: Quad<CX, CY, CZ>,
+    ) -> [([u32; 3], [u32; 2]); 4] {
         let w = quad.width() as u32;
         let h = quad.height() as u32;
-        let xyz = (MASK_XYZ & quad.0) as u32;
+        let [x, y, z] = quad.xyz().map(|c| c as u32);
         match self {
             Face::Left => [
-                Vertex::pack(xyz, h, w),
-                Vertex::pack(xyz + packed_xyz(0, 0, h), 0, w),
-                Vertex::pack(xyz + packed_xyz(0, w, 0), h, 0),
-                Vertex::pack(xyz + packed_xyz(0, w, h), 0, 0),
+                ([x, y, z], [h, w]),
+                ([x, y, z + h], [0, w]),
+                ([x, y + w, z], [h, 0]),
+                ([x, y + w, z + h], [0, 0]),
             ],
             Face::Down => [
-                Vertex::pack(xyz - packed_xyz(w, 0, 0) + packed_xyz(0, 0, h), w, h),
-                Vertex::pack(xyz - packed_xyz(w, 0, 0), w, 0),
-                Vertex::pack(xyz + packed_xyz(0, 0, h), 0, h),
-                Vertex::pack(xyz, 0, 0),
+                ([x.wrapping_sub(w), y, z + h], [w, h]),
+                ([x.wrapping_sub(w), y, z], [w, 0]),
+                ([x, y, z + h], [0, h]),
+                ([x, y, z], [0, 0]),
             ],
             Face::Back => [
-                Vertex::pack(xyz, w, h),
-                Vertex::pack(xyz + packed_xyz(0, h, 0), w, 0),
-                Vertex::pack(xyz + packed_xyz(w, 0, 0), 0, h),
-                Vertex::pack(xyz + packed_xyz(w, h, 0), 0, 0),
+                ([x, y, z], [w, h]),
+                ([x, y + h, z], [w, 0]),
+                ([x + w, y, z], [0, h]),
+                ([x + w, y + h, z], [0, 0]),
             ],
             Face::Right => [
-                Vertex::pack(xyz, 0, 0),
-                Vertex::pack(xyz + packed_xyz(0, 0, h), h, 0),
-                Vertex::pack(xyz - packed_xyz(0, w, 0), 0, w),
-                Vertex::pack(xyz + packed_xyz(0, 0, h) - packed_xyz(0, w, 0), h, w),
+                ([x, y, z], [0, 0]),
+                ([x, y, z + h], [h, 0]),
+                ([x, y.wrapping_sub(w), z], [0, w]),
+                ([x, y.wrapping_sub(w), z + h], [h, w]),
             ],
             Face::Up => [
-                Vertex::pack(xyz + packed_xyz(w, 0, h), w, h),
-                Vertex::pack(xyz + packed_xyz(w, 0, 0), w, 0),
-                Vertex::pack(xyz + packed_xyz(0, 0, h), 0, h),
-                Vertex::pack(xyz, 0, 0),
+                ([x + w, y, z + h], [w, h]),
+                ([x + w, y, z], [w, 0]),
+                ([x, y, z + h], [0, h]),
+                ([x, y, z], [0, 0]),
             ],
             Face::Front => [
-                Vertex::pack(xyz - packed_xyz(w, 0, 0) + packed_xyz(0, h, 0), 0, 0),
-                Vertex::pack(xyz - packed_xyz(w, 0, 0), 0, h),
-                Vertex::pack(xyz + packed_xyz(0, h, 0), w, 0),
-                Vertex::pack(xyz, w, h),
+                ([x.wrapping_sub(w), y + h, z], [0, 0]),
+                ([x.wrapping_sub(w), y, z], [0, h]),
+                ([x, y + h, z], [w, 0]),
+                ([x, y, z], [w, h]),
             ],
         }
     }
+
+    /// Same as [`Face::vertices_packed`], but also returns the smooth light of each vertex:
+    /// the average light of the 4 cells around the vertex in front of the face.
+    /// `light` is a padded light buffer with the same layout as the voxel buffer,
+    /// the quad should be meshed with the same buffer in `FaceAttributes::smooth_light`
+    /// so that merged faces share their vertices' light.
+    pub fn vertices_smooth_light<const CX: usize, const CY: usize, const CZ: usize>(
+        &self,
+        quad: Quad<CX, CY, CZ>,
+        light: &[u8],
+    ) -> ([Vertex; 4], [u8; 4]) {
+        self.vertices_smooth_light_in::<Zxy, CX, CY, CZ>(quad, light)
+    }
+
+    /// Same as [`Face::vertices_smooth_light`] for a light buffer in the layout `L`:
+    /// ```
+    /// use binary_greedy_meshing as bgm;
+    /// let light = vec![15; bgm::Mesher::<16>::CS_P3];
+    /// let quad = bgm::Quad::<16>::pack(0, 1, 0, 1, 1, 1);
+    /// let (_, light) = bgm::Face::Up.vertices_smooth_light_in::<bgm::Yzx, _, _, _>(quad, &light);
+    /// assert_eq!(light, [15; 4]);
+    /// ```
+    pub fn vertices_smooth_light_in<
+        L: Layout,
+        const CX: usize,
+        const CY: usize,
+        const CZ: usize,
+    >(
+        &self,
+        quad: Quad<CX, CY, CZ>,
+        light: &[u8],
+    ) -> ([Vertex; 4], [u8; 4]) {
+        let vertices = self.vertices_packed(quad);
+        let n = self.n();
+        let axis = n.iter().position(|&d| d != 0).unwrap();
+        let strides = layout::strides(L::AXES, [CX + 2, CY + 2, CZ + 2]);
+        let light_at = |p: [i32; 3]| {
+            let index: usize = (0..3).map(|i| (p[i] + 1) as usize * strides[i]).sum();
+            light[index] as u32
+        };
+        let vertex_light = vertices.map(|vertex| {
+            let p = vertex.xyz().map(|c| c as i32);
+            // The cell in front of the face touching the vertex on the - side of both tangent axes
+            let mut cell = p;
+            cell[axis] = if n[axis] > 0 { p[axis] } else { p[axis] - 1 };
+            let (t, u) = ((axis + 1) % 3, (axis + 2) % 3);
+            cell[t] -= 1;
+            cell[u] -= 1;
+            let mut sum = 0;
+            for (dt, du) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let mut c = cell;
+                c[t] += dt;
+                c[u] += du;
+                sum += light_at(c);
+            }
+            (sum / 4) as u8
+        });
+        (vertices, vertex_light)
+    }
 }
@@ -0,0 +1,250 @@
+use crate::{Face, Indices, MeshBuffers, Quad, QuadGroups};
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write as _;
+use std::io::{self, Write};
+
+/// Which attributes [`write_glb`] stores in the vertex colors (COLOR_0, a grey level per vertex),
+/// the color is white if none are set or the quads don't have them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GltfColors {
+    /// The vertices' AO (see [`crate::Quad::ao`]), from 1 (unoccluded) to 0.25 (fully occluded)
+    pub ao: bool,
+    /// The quads' light (see [`crate::Quad::light`]), from 0 to 1 for light values in 0..=15
+    pub light: bool,
+}
+
+impl GltfColors {
+    fn any(&self) -> bool {
+        self.ao || self.light
+    }
+
+    /// The color of the 4 vertices of a quad
+    fn quad<const CX: usize, const CY: usize, const CZ: usize>(
+        &self,
+        quad: Quad<CX, CY, CZ>,
+    ) -> [f32; 4] {
+        let light = match quad.light() {
+            Some(light) if self.light => (light as f32 / 15.).min(1.),
+            _ => 1.,
+        };
+        quad.ao().map(|ao| {
+            if self.ao {
+                light * (1. - ao as f32 / 4.)
+            } else {
+                light
+            }
+        })
+    }
+}
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+/// Writes quads (such as [`crate::ChunkMesher::quads`]) as a binary glTF 2.0 file (.glb), in chunk space.
+///
+/// The file has 1 mesh with 1 primitive per voxel id, each using the material `voxel_<id>`,
+/// with the vertices of [`MeshBuffers`] (positions, normals and UVs in voxels), optional vertex colors (see [`GltfColors`])
+/// and u16 indices when the primitive's quads allow it (see [`Indices`]):
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut voxels = vec![0u16; bgm::Mesher::<16>::CS_P3];
+/// voxels[bgm::pad_linearize::<16>(0, 0, 0)] = 1;
+/// let mut mesher = bgm::Mesher::<16>::new();
+/// mesher.mesh_ao(&voxels, &bgm::TransparentSet::new());
+/// let mut glb = Vec::new();
+/// let colors = bgm::GltfColors { ao: true, light: false };
+/// bgm::write_glb(&mut glb, &mesher.quads, colors).unwrap();
+/// assert_eq!(&glb[..4], b"glTF");
+/// ```
+pub fn write_glb<const CX: usize, const CY: usize, const CZ: usize>(
+    mut writer: impl Write,
+    quads: &[Vec<Quad<CX, CY, CZ>>; 6],
+    colors: GltfColors,
+) -> io::Result<()> {
+    let groups = QuadGroups::new(quads, |quad| quad.voxel_id());
+    let mut bin = BinBuilder::default();
+    let mut primitives = Vec::new();
+    let mut materials = Vec::new();
+    for (material, group) in groups.groups.iter().enumerate() {
+        let mut buffers = MeshBuffers::new();
+        let mut vertex_colors = Vec::new();
+        for (face, range) in group.faces.iter().enumerate() {
+            let quads = &groups.quads[range.clone()];
+            buffers.push_quads(Face::from(face as u8), quads, [0.; 3], 1.);
+            if colors.any() {
+                for &quad in quads {
+                    vertex_colors.extend(colors.quad(quad).map(|c| [c; 3]));
+                }
+            }
+        }
+        let vertices = buffers.positions.len();
+        let (min, max) = buffers.positions.iter().fold(
+            ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+            |(min, max), p| {
+                (
+                    core::array::from_fn(|i| min[i].min(p[i])),
+                    core::array::from_fn(|i| max[i].max(p[i])),
+                )
+            },
+        );
+        let mut attributes = format!(
+            r#""POSITION":{},"NORMAL":{},"TEXCOORD_0":{}"#,
+            bin.accessor(
+                &floats(&buffers.positions),
+                ARRAY_BUFFER,
+                FLOAT,
+                vertices,
+                "VEC3",
+                Some((&min, &max)),
+            ),
+            bin.accessor(
+                &floats(&buffers.normals),
+                ARRAY_BUFFER,
+                FLOAT,
+                vertices,
+                "VEC3",
+                None
+            ),
+            bin.accessor(
+                &floats(&buffers.uvs),
+                ARRAY_BUFFER,
+                FLOAT,
+                vertices,
+                "VEC2",
+                None
+            ),
+        );
+        if colors.any() {
+            let color = bin.accessor(
+                &floats(&vertex_colors),
+                ARRAY_BUFFER,
+                FLOAT,
+                vertices,
+                "VEC3",
+                None,
+            );
+            write!(attributes, r#","COLOR_0":{color}"#).unwrap();
+        }
+        let (indices, component_type, count) = match Indices::new(vertices / 4) {
+            Indices::U16(indices) => (
+                indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+                UNSIGNED_SHORT,
+                indices.len(),
+            ),
+            Indices::U32(indices) => (
+                indices
+                    .iter()
+                    .flat_map(|i| i.to_le_bytes())
+                    .collect::<Vec<_>>(),
+                UNSIGNED_INT,
+                indices.len(),
+            ),
+        };
+        let indices = bin.accessor(
+            &indices,
+            ELEMENT_ARRAY_BUFFER,
+            component_type,
+            count,
+            "SCALAR",
+            None,
+        );
+        primitives.push(format!(
+            r#"{{"attributes":{{{attributes}}},"indices":{indices},"material":{material}}}"#
+        ));
+        materials.push(format!(
+            r#"{{"name":"voxel_{}","pbrMetallicRoughness":{{"metallicFactor":0}}}}"#,
+            group.key
+        ));
+    }
+
+    let mut json =
+        String::from(r#"{"asset":{"version":"2.0","generator":"binary-greedy-meshing"},"scene":0"#);
+    if primitives.is_empty() {
+        json.push_str(r#","scenes":[{"nodes":[]}]}"#);
+    } else {
+        write!(
+            json,
+            r#","scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"meshes":[{{"primitives":[{}]}}],"materials":[{}],"buffers":[{{"byteLength":{}}}],"bufferViews":[{}],"accessors":[{}]}}"#,
+            primitives.join(","),
+            materials.join(","),
+            bin.data.len(),
+            bin.views.join(","),
+            bin.accessors.join(","),
+        )
+        .unwrap();
+    }
+    // Chunks are 4 bytes aligned, JSON is padded with spaces and binary data with zeros
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut length = 12 + 8 + json.len();
+    if !bin.data.is_empty() {
+        length += 8 + bin.data.len();
+    }
+    writer.write_all(b"glTF")?;
+    writer.write_all(&2u32.to_le_bytes())?;
+    writer.write_all(&(length as u32).to_le_bytes())?;
+    writer.write_all(&(json.len() as u32).to_le_bytes())?;
+    writer.write_all(b"JSON")?;
+    writer.write_all(&json)?;
+    if !bin.data.is_empty() {
+        writer.write_all(&(bin.data.len() as u32).to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&bin.data)?;
+    }
+    writer.flush()
+}
+
+fn floats<const N: usize>(values: &[[f32; N]]) -> Vec<u8> {
+    values
+        .iter()
+        .flatten()
+        .flat_map(|f| f.to_le_bytes())
+        .collect()
+}
+
+/// The binary chunk, with 1 buffer view per accessor
+#[derive(Default)]
+struct BinBuilder {
+    data: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl BinBuilder {
+    /// Adds the data of an accessor and returns its index
+    fn accessor(
+        &mut self,
+        bytes: &[u8],
+        target: u32,
+        component_type: u32,
+        count: usize,
+        kind: &str,
+        min_max: Option<(&[f32; 3], &[f32; 3])>,
+    ) -> usize {
+        let index = self.accessors.len();
+        self.views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{target}}}"#,
+            self.data.len(),
+            bytes.len()
+        ));
+        self.data.extend_from_slice(bytes);
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+        let mut accessor = format!(
+            r#"{{"bufferView":{index},"componentType":{component_type},"count":{count},"type":"{kind}""#
+        );
+        if let Some((min, max)) = min_max {
+            write!(
+                accessor,
+                r#","min":[{},{},{}],"max":[{},{},{}]"#,
+                min[0], min[1], min[2], max[0], max[1], max[2]
+            )
+            .unwrap();
+        }
+        accessor.push('}');
+        self.accessors.push(accessor);
+        index
+    }
+}
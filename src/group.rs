@@ -0,0 +1,103 @@
+use crate::{Face, Quad};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The quads of a chunk sorted by a material key, so that each material can be drawn with 1 draw call:
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut voxels = vec![0u16; bgm::Mesher::<16>::CS_P3];
+/// voxels[bgm::pad_linearize::<16>(0, 0, 0)] = 1;
+/// voxels[bgm::pad_linearize::<16>(4, 0, 0)] = 2;
+/// let mut mesher = bgm::Mesher::<16>::new();
+/// mesher.mesh(&voxels, &bgm::TransparentSet::new());
+/// let groups = bgm::QuadGroups::new(&mesher.quads, |quad| quad.voxel_id());
+/// assert_eq!(groups.get(&2).unwrap().len(), 6);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuadGroups<K, const CX: usize, const CY: usize = CX, const CZ: usize = CX> {
+    /// The quads sorted by key then by face, quads with the same key and face keep their meshing order
+    pub quads: Vec<Quad<CX, CY, CZ>>,
+    /// The groups sorted by key
+    pub groups: Vec<QuadGroup<K>>,
+}
+
+/// The quads of 1 material in [`QuadGroups::quads`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuadGroup<K> {
+    pub key: K,
+    /// The range of the group's quads
+    pub range: Range<usize>,
+    /// The range of the group's quads of each face (indexed by [`Face`]), in `range`
+    pub faces: [Range<usize>; 6],
+}
+
+impl<K: Ord, const CX: usize, const CY: usize, const CZ: usize> QuadGroups<K, CX, CY, CZ> {
+    /// Groups the quads of each face (such as [`crate::ChunkMesher::quads`]) by the key of each quad
+    pub fn new(quads: &[Vec<Quad<CX, CY, CZ>>; 6], key: impl Fn(Quad<CX, CY, CZ>) -> K) -> Self {
+        let mut keyed: Vec<_> = quads
+            .iter()
+            .enumerate()
+            .flat_map(|(face, quads)| quads.iter().map(move |&quad| (face, quad)))
+            .map(|(face, quad)| (key(quad), face, quad))
+            .collect();
+        // The sort is stable so quads keep their order within their key and face
+        keyed.sort_by(|(key1, face1, _), (key2, face2, _)| key1.cmp(key2).then(face1.cmp(face2)));
+        let mut groups: Vec<QuadGroup<K>> = Vec::new();
+        // The number of quads of each face in each group
+        let mut face_counts: Vec<[usize; 6]> = Vec::new();
+        let quads = keyed
+            .into_iter()
+            .enumerate()
+            .map(|(i, (key, face, quad))| {
+                match groups.last_mut() {
+                    Some(group) if group.key == key => group.range.end = i + 1,
+                    _ => {
+                        groups.push(QuadGroup {
+                            key,
+                            range: i..(i + 1),
+                            faces: core::array::from_fn(|_| 0..0),
+                        });
+                        face_counts.push([0; 6]);
+                    }
+                }
+                face_counts.last_mut().unwrap()[face] += 1;
+                quad
+            })
+            .collect();
+        for (group, counts) in groups.iter_mut().zip(face_counts) {
+            let mut start = group.range.start;
+            group.faces = counts.map(|count| {
+                start += count;
+                (start - count)..start
+            });
+        }
+        Self { quads, groups }
+    }
+
+    /// The quads with the given key
+    pub fn get(&self, key: &K) -> Option<&[Quad<CX, CY, CZ>]> {
+        let i = self
+            .groups
+            .binary_search_by(|group| group.key.cmp(key))
+            .ok()?;
+        Some(&self.quads[self.groups[i].range.clone()])
+    }
+
+    /// The quads of each group and face, as (key, face, quads)
+    pub fn iter(&self) -> impl Iterator<Item = (&K, Face, &[Quad<CX, CY, CZ>])> {
+        self.groups.iter().flat_map(move |group| {
+            group
+                .faces
+                .iter()
+                .enumerate()
+                .filter(|(_, range)| !range.is_empty())
+                .map(move |(face, range)| {
+                    (
+                        &group.key,
+                        Face::from(face as u8),
+                        &self.quads[range.clone()],
+                    )
+                })
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use alloc::vec::Vec;
+
+/// Compute Mesh indices for a given amount of quads
+pub fn indices(num_quads: usize) -> Vec<u32> {
+    // Each quads is made of 2 triangles which require 6 indices
+    // The indices are the same regardless of the face
+    let mut res = Vec::with_capacity(num_quads * 6);
+    for i in 0..num_quads as u32 {
+        res.extend(quad_indices(i));
+    }
+    res
+}
+
+/// The 6 indices of the quad `i`, whose vertices are 4*i..4*i+4
+#[inline]
+pub(crate) fn quad_indices(i: u32) -> [u32; 6] {
+    [
+        (i << 2) | 2,
+        i << 2,
+        (i << 2) | 1,
+        (i << 2) | 1,
+        (i << 2) | 3,
+        (i << 2) | 2,
+    ]
+}
+
+/// The most quads whose vertices (4 per quad) can be indexed with u16 in a triangle list
+pub const U16_MAX_QUADS: usize = (u16::MAX as usize + 1) / 4;
+
+/// The most quads that fit u16 triangle strips, 1 less than [`U16_MAX_QUADS`] because u16::MAX is the restart index
+pub const U16_MAX_STRIP_QUADS: usize = U16_MAX_QUADS - 1;
+
+/// Same as [`indices`] with u16 indices, for at most [`U16_MAX_QUADS`] quads
+pub fn indices_u16(num_quads: usize) -> Vec<u16> {
+    assert!(num_quads <= U16_MAX_QUADS, "too many quads for u16 indices");
+    let mut res = Vec::with_capacity(num_quads * 6);
+    for i in 0..num_quads as u32 {
+        res.extend(quad_indices(i).map(|index| index as u16));
+    }
+    res
+}
+
+/// The 6 indices of the quad `i`, whose vertices are 4*i..4*i+4, split along the diagonal whose 2 vertices
+/// are the least occluded. `ao` is the AO of the 4 vertices in the order of [`crate::Face::vertices_packed`]
+/// (such as [`crate::Quad::ao`] or [`crate::ChunkMesher::corner_ao`]).
+///
+/// A quad's colors are interpolated over its 2 triangles separately, so with the fixed diagonal of [`indices`]
+/// the AO of a vertex can spread along the diagonal to the opposite corner, depending on the quad's orientation
+pub fn quad_indices_ao(i: u32, ao: [u8; 4]) -> [u32; 6] {
+    if (ao[0] + ao[3]) < (ao[1] + ao[2]) {
+        [
+            i << 2,
+            (i << 2) | 1,
+            (i << 2) | 3,
+            (i << 2) | 3,
+            (i << 2) | 2,
+            i << 2,
+        ]
+    } else {
+        quad_indices(i)
+    }
+}
+
+/// Triangle list indices with the diagonal of each quad picked from its vertices' AO (see [`quad_indices_ao`])
+pub fn indices_ao(ao: impl IntoIterator<Item = [u8; 4]>) -> Vec<u32> {
+    ao.into_iter()
+        .enumerate()
+        .flat_map(|(i, ao)| quad_indices_ao(i as u32, ao))
+        .collect()
+}
+
+/// Triangle strip indices for `num_quads` quads (same vertices and winding as [`indices`]),
+/// each quad is a strip of 4 indices and quads are separated by the restart index u32::MAX,
+/// so it needs primitive restart (enabled by default with strips in WebGPU and Vulkan, fixed index in OpenGL)
+pub fn strip_indices(num_quads: usize) -> Vec<u32> {
+    let mut res = Vec::with_capacity((num_quads * 5).saturating_sub(1));
+    for i in 0..num_quads as u32 {
+        if i > 0 {
+            res.push(u32::MAX);
+        }
+        res.extend([i << 2, (i << 2) | 1, (i << 2) | 2, (i << 2) | 3]);
+    }
+    res
+}
+
+/// Same as [`strip_indices`] with u16 indices and the restart index u16::MAX, for at most [`U16_MAX_STRIP_QUADS`] quads
+pub fn strip_indices_u16(num_quads: usize) -> Vec<u16> {
+    assert!(
+        num_quads <= U16_MAX_STRIP_QUADS,
+        "too many quads for u16 strip indices"
+    );
+    strip_indices(num_quads)
+        .into_iter()
+        .map(|index| index as u16)
+        .collect()
+}
+
+/// An index buffer using u16 indices when the quads allow it, halving its size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// Triangle list indices for `num_quads` quads (see [`indices`]),
+    /// u16 for up to [`U16_MAX_QUADS`] quads:
+    /// ```
+    /// use binary_greedy_meshing as bgm;
+    /// assert!(matches!(bgm::Indices::new(100), bgm::Indices::U16(_)));
+    /// assert!(matches!(bgm::Indices::new(20000), bgm::Indices::U32(_)));
+    /// ```
+    pub fn new(num_quads: usize) -> Self {
+        if num_quads <= U16_MAX_QUADS {
+            Self::U16(indices_u16(num_quads))
+        } else {
+            Self::U32(crate::indices(num_quads))
+        }
+    }
+
+    /// Triangle strip indices for `num_quads` quads (see [`strip_indices`]),
+    /// u16 for up to [`U16_MAX_STRIP_QUADS`] quads
+    pub fn strip(num_quads: usize) -> Self {
+        if num_quads <= U16_MAX_STRIP_QUADS {
+            Self::U16(strip_indices_u16(num_quads))
+        } else {
+            Self::U32(strip_indices(num_quads))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The triangle list indices are the same for every chunk, only their count changes,
+/// so a single buffer can be shared by all chunks, drawing the first 6 indices per quad.
+/// This keeps that buffer and grows it when a chunk has more quads than before:
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut cache = bgm::IndexCache::new();
+/// assert_eq!(cache.get_u32(1000), bgm::indices(1000));
+/// assert_eq!(cache.get_u32(10), bgm::indices(10));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IndexCache {
+    u16: Vec<u16>,
+    u32: Vec<u32>,
+}
+
+impl IndexCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The u32 indices of `num_quads` quads
+    pub fn get_u32(&mut self, num_quads: usize) -> &[u32] {
+        let quads = self.u32.len() / 6;
+        if num_quads > quads {
+            self.u32.reserve((num_quads - quads) * 6);
+            for i in quads as u32..num_quads as u32 {
+                self.u32.extend(quad_indices(i));
+            }
+        }
+        &self.u32[..num_quads * 6]
+    }
+
+    /// The u16 indices of `num_quads` quads, at most [`U16_MAX_QUADS`]
+    pub fn get_u16(&mut self, num_quads: usize) -> &[u16] {
+        assert!(num_quads <= U16_MAX_QUADS, "too many quads for u16 indices");
+        let quads = self.u16.len() / 6;
+        if num_quads > quads {
+            self.u16.reserve((num_quads - quads) * 6);
+            for i in quads as u32..num_quads as u32 {
+                self.u16.extend(quad_indices(i).map(|index| index as u16));
+            }
+        }
+        &self.u16[..num_quads * 6]
+    }
+
+    /// The u16 indices of `num_quads` quads if they fit, the u32 ones otherwise
+    pub fn get(&mut self, num_quads: usize) -> IndexSlice<'_> {
+        if num_quads <= U16_MAX_QUADS {
+            IndexSlice::U16(self.get_u16(num_quads))
+        } else {
+            IndexSlice::U32(self.get_u32(num_quads))
+        }
+    }
+}
+
+/// Indices borrowed from an [`IndexCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexSlice<'a> {
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+}
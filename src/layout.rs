@@ -0,0 +1,52 @@
+/// The order of the voxels in voxel buffers (padded or not) and in light buffers,
+/// named from the fastest to the slowest axis: with [`Zxy`] (the default) the voxel x;y;z is at z + x*CZ + y*CZ*CX.
+///
+/// Only the voxel buffers follow the layout, the opaque and transparent masks always have 1 column along z
+/// per x;y (at index x + y*CX_P) whatever the layout.
+///
+/// This trait is sealed and implemented by [`Zxy`], [`Xzy`] and [`Yzx`].
+pub trait Layout: sealed::Sealed {
+    /// The axes (0 for x, 1 for y, 2 for z) from the fastest to the slowest
+    const AXES: [usize; 3];
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// z + x*CZ + y*CZ*CX, the default layout
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zxy;
+
+/// x + z*CX + y*CX*CZ
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Xzy;
+
+/// y + z*CY + x*CY*CZ
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yzx;
+
+impl sealed::Sealed for Zxy {}
+impl sealed::Sealed for Xzy {}
+impl sealed::Sealed for Yzx {}
+
+impl Layout for Zxy {
+    const AXES: [usize; 3] = [2, 0, 1];
+}
+
+impl Layout for Xzy {
+    const AXES: [usize; 3] = [0, 2, 1];
+}
+
+impl Layout for Yzx {
+    const AXES: [usize; 3] = [1, 2, 0];
+}
+
+/// The strides of the x, y and z axes in a buffer of size `dims` laid out as `axes`
+pub(crate) const fn strides(axes: [usize; 3], dims: [usize; 3]) -> [usize; 3] {
+    let mut strides = [0; 3];
+    strides[axes[0]] = 1;
+    strides[axes[1]] = dims[axes[0]];
+    strides[axes[2]] = dims[axes[0]] * dims[axes[1]];
+    strides
+}
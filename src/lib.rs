@@ -2,232 +2,795 @@
 
 #[macro_use]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+mod bounds;
+mod buffers;
+mod chunk;
 mod face;
+#[cfg(feature = "gltf")]
+mod glb;
+mod group;
+mod indices;
+mod layout;
+mod mask;
+#[cfg(feature = "obj")]
+mod obj;
 mod quad;
+mod sort;
+mod voxel;
 
-use alloc::{boxed::Box, collections::btree_set::BTreeSet, vec::Vec};
+use alloc::{boxed::Box, vec::Vec};
+use core::{marker::PhantomData, ops::Range};
 
+pub use bounds::*;
+pub use buffers::*;
+pub use chunk::*;
 pub use face::*;
+#[cfg(feature = "gltf")]
+pub use glb::*;
+pub use group::*;
+pub use indices::*;
+pub use layout::*;
+pub use mask::*;
+#[cfg(feature = "obj")]
+pub use obj::*;
 pub use quad::*;
+pub use sort::*;
+pub use voxel::*;
 
+/// Meshes chunks of size CS^3 (CS_P^3 with padding), see [`ChunkMesher`] for non-cubic chunks.
+///
+/// How CS relates to the rest of the crate:
+/// - voxel buffers are CS_P^3 with CS_P = CS + 2, 1 voxel of padding on each side
+/// - masks have 1 `M` per padded column, so CS_P must fit in `M::BITS`.
+///   `M` is chosen from CS by default (see [`ChunkSize`]), a custom `M` is checked at compile time:
+/// ```compile_fail
+/// let mesher = binary_greedy_meshing::Mesher::<62, u32>::new();
+/// ```
+/// - output quads are `Quad<CS>`, their fields are [`Quad::X_BITS`] wide, enough to hold CS
+///
+/// Small chunks (CS <= 30) use u32 masks, which halves the face mask buffers, and 5 bit quad fields.
+/// This saves memory but meshing isn't measurably faster than with u64 masks (see the `fast_mesh_small` benches),
+/// and quads are still a u64 each.
+pub type Mesher<const CS: usize, M = MaskOf<CS>, L = Zxy> = ChunkMesher<CS, CS, CS, M, L>;
+
+/// Meshes chunks of size CX*CY*CZ (CX_P*CY_P*CZ_P with padding).
+///
+/// Voxel buffers are in ZXY order by default, `L` picks another order (see [`Layout`] and [`ChunkMesher::pad_linearize`]).
+/// Mask columns run along z so only CZ is bounded by the mask width (CZ + 2 <= `M::BITS`),
+/// CX and CY can go up to 256, which allows tall chunks such as 32x256x32:
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut mesher = bgm::ChunkMesher::<32, 256, 32>::new();
+/// let mut voxels = vec![0u16; bgm::ChunkMesher::<32, 256, 32>::VOXELS_P];
+/// voxels[bgm::ChunkMesher::<32, 256, 32>::pad_linearize(0, 255, 0)] = 1;
+/// mesher.mesh(&voxels, &bgm::TransparentSet::new());
+/// assert_eq!(mesher.quads[bgm::Face::Up as usize][0].xyz(), [0, 256, 0]);
+/// ```
 #[derive(Debug)]
-pub struct Mesher<const CS: usize> {
+pub struct ChunkMesher<
+    const CX: usize,
+    const CY: usize,
+    const CZ: usize,
+    M: Mask = MaskOf<CZ>,
+    L: Layout = Zxy,
+> {
     // Output
-    pub quads: [Vec<Quad>; 6],
+    pub quads: [Vec<Quad<CX, CY, CZ>>; 6],
+    /// The quads of transparent voxels if `split_transparent` is set, otherwise they're in `quads`
+    pub transparent_quads: [Vec<Quad<CX, CY, CZ>>; 6],
+    // Options
+    /// Outputs the quads of transparent voxels in `transparent_quads` instead of `quads`
+    /// (classified like in culling, by the transparent mask or the classifier),
+    /// faces of opaque and transparent voxels are then never merged together
+    pub split_transparent: bool,
+    /// The min and max corners of each face group of `quads` and `transparent_quads`
+    bounds: [[([u32; 3], [u32; 3]); 6]; 2],
     // Internal buffers
-    /// CS_2 * 6
-    face_masks: Box<[u64]>,
-    /// CS_2
+    /// CX * CY * 6
+    face_masks: Box<[M]>,
+    /// CX * CZ
     forward_merged: Box<[u8]>,
-    /// CS
+    /// CZ
     right_merged: Box<[u8]>,
+    layout: PhantomData<L>,
 }
 
-impl<const CS: usize> Mesher<CS> {
+impl<const CS: usize, M: Mask, L: Layout> ChunkMesher<CS, CS, CS, M, L> {
     pub const CS_2: usize = CS * CS;
     pub const CS_P: usize = CS + 2;
     pub const CS_P2: usize = Self::CS_P * Self::CS_P;
     pub const CS_P3: usize = Self::CS_P * Self::CS_P * Self::CS_P;
-    pub const P_MASK: u64 = !(1 << (Self::CS_P - 1) | 1);
+}
+
+impl<const CX: usize, const CY: usize, const CZ: usize, M: Mask, L: Layout>
+    ChunkMesher<CX, CY, CZ, M, L>
+{
+    pub const CX_P: usize = CX + 2;
+    pub const CY_P: usize = CY + 2;
+    pub const CZ_P: usize = CZ + 2;
+    /// Length of the opaque and transparent masks, 1 `M` per padded column
+    pub const COLUMNS_P: usize = Self::CX_P * Self::CY_P;
+    /// Length of the padded voxel buffer
+    pub const VOXELS_P: usize = Self::COLUMNS_P * Self::CZ_P;
+    /// Length of the face masks of 1 face
+    const FACE_LEN: usize = CX * CY;
+    const STRIDES: [usize; 3] = layout::strides(L::AXES, [Self::CX_P, Self::CY_P, Self::CZ_P]);
+    const STRIDE_X: usize = Self::STRIDES[0];
+    const STRIDE_Y: usize = Self::STRIDES[1];
+    const STRIDE_Z: usize = Self::STRIDES[2];
+    /// Strides of an unpadded voxel buffer
+    const UNPADDED_STRIDES: [usize; 3] = layout::strides(L::AXES, [CX, CY, CZ]);
+
+    /// Rejects unsupported dimensions and mask types at compile time,
+    /// evaluated by every public associated function through [`ChunkMesher::assert_dims`]
+    const DIMS_CHECK: () = {
+        assert!(
+            CX > 0 && CY > 0 && CZ > 0,
+            "chunk dimensions must be at least 1"
+        );
+        assert!(
+            CX <= 256 && CY <= 256,
+            "CX and CY can't exceed 256, the merge counters are u8"
+        );
+        assert!(
+            CZ + 2 <= M::BITS,
+            "the padded column (CZ + 2) doesn't fit in the mask type"
+        );
+    };
+
+    #[inline(always)]
+    pub(crate) fn assert_dims() {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::DIMS_CHECK;
+    }
 
     /// Creates a mesher object, allocates necessary buffers
     pub fn new() -> Self {
+        Self::assert_dims();
         Self {
-            face_masks: vec![0; Self::CS_2 * 6].into_boxed_slice(),
-            forward_merged: vec![0; Self::CS_2].into_boxed_slice(),
-            right_merged: vec![0; CS].into_boxed_slice(),
+            face_masks: vec![M::ZERO; Self::FACE_LEN * 6].into_boxed_slice(),
+            forward_merged: vec![0; CX * CZ].into_boxed_slice(),
+            right_merged: vec![0; CZ].into_boxed_slice(),
             quads: core::array::from_fn(|_| Vec::new()),
+            transparent_quads: core::array::from_fn(|_| Vec::new()),
+            split_transparent: false,
+            bounds: [[Self::EMPTY_BOUNDS; 6]; 2],
+            layout: PhantomData,
         }
     }
 
     /// Call this between each meshing call to reset the buffers without reallocating them
     pub fn clear(&mut self) {
-        self.face_masks.fill(0);
+        self.face_masks.fill(M::ZERO);
         self.forward_merged.fill(0);
         self.right_merged.fill(0);
         for i in 0..self.quads.len() {
             self.quads[i].clear();
+            self.transparent_quads[i].clear();
         }
+        self.bounds = [[Self::EMPTY_BOUNDS; 6]; 2];
     }
 
-    fn face_culling(&mut self, voxels: &[u16], transparents: &BTreeSet<u16>) {
-        // Hidden face culling
-        for a in 1..(Self::CS_P - 1) {
-            let a_cs_p = a * Self::CS_P;
+    /// Index of the voxel x;y;z (in 0..CX;0..CY;0..CZ) in a padded voxel buffer
+    #[inline]
+    pub fn pad_linearize(x: usize, y: usize, z: usize) -> usize {
+        Self::assert_dims();
+        Self::padded_index(x + 1, y + 1, z + 1)
+    }
+
+    /// Index of the voxel x;y;z (in 0..CX;0..CY;0..CZ) in an unpadded voxel buffer
+    #[inline]
+    pub fn linearize(x: usize, y: usize, z: usize) -> usize {
+        Self::assert_dims();
+        let [sx, sy, sz] = Self::UNPADDED_STRIDES;
+        x * sx + y * sy + z * sz
+    }
+
+    /// Index of the voxel x;y;z in padded coordinates (in 0..CX_P;0..CY_P;0..CZ_P) in a padded voxel buffer
+    #[inline]
+    pub(crate) fn padded_index(x: usize, y: usize, z: usize) -> usize {
+        x * Self::STRIDE_X + y * Self::STRIDE_Y + z * Self::STRIDE_Z
+    }
+
+    /// The AO of the 4 vertices of a quad (in the order of [`Face::vertices_packed`], as [`Quad::ao`]),
+    /// computed from the opaque voxels of the padded voxel buffer around each corner of the quad,
+    /// so it works on quads meshed without AO, merged or not
+    pub fn corner_ao<V: Voxel>(
+        face: Face,
+        quad: Quad<CX, CY, CZ>,
+        voxels: &[V],
+        classifier: &impl VoxelClassifier<V>,
+    ) -> [u8; 4] {
+        Self::assert_dims();
+        let n = face.n();
+        let axis = n.iter().position(|&d| d != 0).unwrap();
+        let (t, u) = ((axis + 1) % 3, (axis + 2) % 3);
+        let (min, _) = face.quad_extents(quad);
+        // Whether the cell at p (in unpadded coordinates) casts occlusion
+        let occludes = |p: [i32; 3]| {
+            let [x, y, z] = p.map(|c| (c + 1) as usize);
+            classifier.classify(voxels[Self::padded_index(x, y, z)]) == VoxelKind::Opaque
+        };
+        face.quad_corners(quad).map(|(p, _)| {
+            let p = p.map(|c| c as i32);
+            // The cell in front of the quad at this corner, the directions out of the quad on both tangent axes
+            let mut cell = p;
+            cell[axis] = if n[axis] > 0 { p[axis] } else { p[axis] - 1 };
+            let dt = if p[t] == min[t] as i32 { -1 } else { 1 };
+            let du = if p[u] == min[u] as i32 { -1 } else { 1 };
+            cell[t] -= (dt > 0) as i32;
+            cell[u] -= (du > 0) as i32;
+            // The cells next to it out of the quad
+            let mut side1 = cell;
+            side1[t] += dt;
+            let mut side2 = cell;
+            side2[u] += du;
+            let mut corner = side1;
+            corner[u] += du;
+            let (side1, side2) = (occludes(side1), occludes(side2));
+            if side1 && side2 {
+                3
+            } else {
+                side1 as u8 + side2 as u8 + occludes(corner) as u8
+            }
+        })
+    }
+
+    /// Triangle list indices for the quads of each face (such as `quads` or `transparent_quads`, in that order),
+    /// with the diagonal of each quad picked from the AO of its corners computed from the padded voxel buffer
+    /// (see [`ChunkMesher::corner_ao`] and [`quad_indices_ao`])
+    pub fn ao_indices<V: Voxel>(
+        quads: &[Vec<Quad<CX, CY, CZ>>; 6],
+        voxels: &[V],
+        classifier: &impl VoxelClassifier<V>,
+    ) -> Vec<u32> {
+        Self::assert_dims();
+        indices_ao(quads.iter().enumerate().flat_map(|(face, quads)| {
+            let face = Face::from(face as u8);
+            quads
+                .iter()
+                .map(move |&quad| Self::corner_ao(face, quad, voxels, classifier))
+        }))
+    }
+
+    /// The mask column and bit of the voxel at `index` in a padded voxel buffer
+    #[inline]
+    fn mask_position(index: usize) -> (usize, usize) {
+        if Self::STRIDE_Z == 1 {
+            // ZXY, the columns are contiguous
+            return (index / Self::CZ_P, index % Self::CZ_P);
+        }
+        let x = index / Self::STRIDE_X % Self::CX_P;
+        let y = index / Self::STRIDE_Y % Self::CY_P;
+        let z = index / Self::STRIDE_Z % Self::CZ_P;
+        (x + y * Self::CX_P, z)
+    }
+
+    /// The bit of the voxel at `index` (in a padded voxel buffer) in a mask
+    #[inline]
+    fn mask_bit(mask: &[M], index: usize) -> bool {
+        let (column, z) = Self::mask_position(index);
+        (mask[column] >> z & M::ONE) != M::ZERO
+    }
+
+    /// Compute an opacity mask from a voxel buffer and a classifier (such as a [`TransparentSet`]),
+    /// the mask type must fit the padded columns:
+    /// ```compile_fail
+    /// use binary_greedy_meshing as bgm;
+    /// let voxels = vec![0u16; bgm::Mesher::<62>::CS_P3];
+    /// let mask = bgm::ChunkMesher::<62, 62, 62, u32>::compute_opaque_mask(&voxels, &bgm::TransparentSet::new());
+    /// ```
+    pub fn compute_opaque_mask<V: Voxel>(
+        voxels: &[V],
+        classifier: &impl VoxelClassifier<V>,
+    ) -> Box<[M]> {
+        Self::compute_mask(voxels, classifier, VoxelKind::Opaque)
+    }
+
+    /// Compute a transparent mask from a voxel buffer and a classifier (such as a [`TransparentSet`])
+    pub fn compute_transparent_mask<V: Voxel>(
+        voxels: &[V],
+        classifier: &impl VoxelClassifier<V>,
+    ) -> Box<[M]> {
+        Self::compute_mask(voxels, classifier, VoxelKind::Transparent)
+    }
 
-            for b in 1..(Self::CS_P - 1) {
-                let ab = (a_cs_p + b) * Self::CS_P;
-                let ba_index = (b - 1) + (a - 1) * CS;
-                let ab_index = (a - 1) + (b - 1) * CS;
+    /// Compute the mask of the voxels of the given kind
+    fn compute_mask<V: Voxel>(
+        voxels: &[V],
+        classifier: &impl VoxelClassifier<V>,
+        kind: VoxelKind,
+    ) -> Box<[M]> {
+        Self::assert_dims();
+        assert_eq!(voxels.len(), Self::VOXELS_P);
+        let mut mask = vec![M::ZERO; Self::COLUMNS_P].into_boxed_slice();
+        for y in 0..Self::CY_P {
+            for x in 0..Self::CX_P {
+                let column = x + y * Self::CX_P;
+                for z in 0..Self::CZ_P {
+                    if classifier.classify(voxels[Self::padded_index(x, y, z)]) == kind {
+                        mask[column] |= M::ONE << z;
+                    }
+                }
+            }
+        }
+        mask
+    }
+
+    /// Index of the voxel at padded coordinates a;b;c along `axis`, in a padded voxel buffer if `PADDED`
+    /// or in an unpadded one (only valid for voxels inside the chunk)
+    #[inline]
+    fn get_axis_index<const PADDED: bool>(axis: usize, a: usize, b: usize, c: usize) -> usize {
+        let (x, y, z) = match axis {
+            0 => (a, c, b),
+            1 => (c, a, b),
+            _ => (a, b, c),
+        };
+        if PADDED {
+            Self::padded_index(x, y, z)
+        } else {
+            Self::linearize(x - 1, y - 1, z - 1)
+        }
+    }
+
+    /// Mask of the unpadded part of a column
+    #[inline]
+    fn p_mask() -> M {
+        !(M::ONE << (Self::CZ_P - 1) | M::ONE)
+    }
+
+    /// Every layer of the chunk, on the x, y and z axes
+    const ALL_LAYERS: [Range<usize>; 3] = [0..CX, 0..CY, 0..CZ];
+
+    /// Mask of the voxels of a padded column with their z in `zs`
+    #[inline]
+    fn z_mask(zs: &Range<usize>) -> M {
+        if zs.is_empty() {
+            return M::ZERO;
+        }
+        (!M::ZERO >> (M::BITS - zs.len())) << (zs.start + 1)
+    }
+
+    fn face_culling<V: Voxel>(&mut self, voxels: &[V], classifier: &impl VoxelClassifier<V>) {
+        // Hidden face culling
+        for a in 1..(Self::CY_P - 1) {
+            for b in 1..(Self::CX_P - 1) {
+                let ab = Self::padded_index(b, a, 0);
+                let ba_index = (b - 1) + (a - 1) * CX;
+                let ab_index = (a - 1) + (b - 1) * CY;
+                let up_faces = ba_index;
+                let down_faces = ba_index + Self::FACE_LEN;
+                let right_faces = ab_index + 2 * Self::FACE_LEN;
+                let left_faces = ab_index + 3 * Self::FACE_LEN;
+                let front_faces = ba_index + 4 * Self::FACE_LEN;
+                let back_faces = ba_index + 5 * Self::FACE_LEN;
 
-                for c in 1..(Self::CS_P - 1) {
-                    let abc = ab + c;
+                for c in 1..(Self::CZ_P - 1) {
+                    let abc = ab + c * Self::STRIDE_Z;
                     let v1 = voxels[abc];
-                    if v1 == 0 {
+                    if classifier.classify(v1) == VoxelKind::Air {
                         continue;
                     }
-                    self.face_masks[ba_index + 0 * Self::CS_2] |=
-                        face_value(v1, voxels[abc + Self::CS_P2], &transparents) << (c - 1);
-                    self.face_masks[ba_index + 1 * Self::CS_2] |=
-                        face_value(v1, voxels[abc - Self::CS_P2], &transparents) << (c - 1);
-
-                    self.face_masks[ab_index + 2 * Self::CS_2] |=
-                        face_value(v1, voxels[abc + Self::CS_P], &transparents) << (c - 1);
-                    self.face_masks[ab_index + 3 * Self::CS_2] |=
-                        face_value(v1, voxels[abc - Self::CS_P], &transparents) << (c - 1);
-
-                    self.face_masks[ba_index + 4 * Self::CS_2] |=
-                        face_value(v1, voxels[abc + 1], &transparents) << c;
-                    self.face_masks[ba_index + 5 * Self::CS_2] |=
-                        face_value(v1, voxels[abc - 1], &transparents) << c;
+                    self.face_masks[up_faces] |=
+                        face_value::<M, V>(v1, voxels[abc + Self::STRIDE_Y], classifier) << (c - 1);
+                    self.face_masks[down_faces] |=
+                        face_value::<M, V>(v1, voxels[abc - Self::STRIDE_Y], classifier) << (c - 1);
+
+                    self.face_masks[right_faces] |=
+                        face_value::<M, V>(v1, voxels[abc + Self::STRIDE_X], classifier) << (c - 1);
+                    self.face_masks[left_faces] |=
+                        face_value::<M, V>(v1, voxels[abc - Self::STRIDE_X], classifier) << (c - 1);
+
+                    self.face_masks[front_faces] |=
+                        face_value::<M, V>(v1, voxels[abc + Self::STRIDE_Z], classifier) << c;
+                    self.face_masks[back_faces] |=
+                        face_value::<M, V>(v1, voxels[abc - Self::STRIDE_Z], classifier) << c;
                 }
             }
         }
     }
 
-    fn fast_face_culling(&mut self, voxels: &[u16], opaque_mask: &[u64], trans_mask: &[u64]) {
+    /// Only the face masks of the given layers (on the x, y and z axes) are computed,
+    /// the other layers are left in an unspecified state
+    fn fast_face_culling<V: Voxel>(
+        &mut self,
+        voxels: &[V],
+        opaque_mask: &[M],
+        trans_mask: &[M],
+        layers: &[Range<usize>; 3],
+    ) {
+        let p_mask = Self::p_mask();
+        let [xs, ys, _] = layers;
         // Hidden face culling
-        for a in 1..(Self::CS_P - 1) {
-            let a_ = a * Self::CS_P;
-
-            for b in 1..(Self::CS_P - 1) {
-                // Column-wise opaque step
-                let ab = a_ + b;
-                let opaque_col = opaque_mask[ab] & Self::P_MASK;
-                let unpadded_opaque_col = opaque_col >> 1;
-                let ba_index = (b - 1) + (a - 1) * CS;
-                let ab_index = (a - 1) + (b - 1) * CS;
-                let up_faces = ba_index + 0 * Self::CS_2;
-                let down_faces = ba_index + 1 * Self::CS_2;
-                let right_faces = ab_index + 2 * Self::CS_2;
-                let left_faces = ab_index + 3 * Self::CS_2;
-                let front_faces = ba_index + 4 * Self::CS_2;
-                let back_faces = ba_index + 5 * Self::CS_2;
-                let not_front_col = !opaque_mask[ab + Self::CS_P] >> 1;
-                let not_back_col = !opaque_mask[ab - Self::CS_P] >> 1;
-                let not_right_col = !opaque_mask[ab + 1] >> 1;
-                let not_left_col = !opaque_mask[ab - 1] >> 1;
-                let not_col_up = !(opaque_mask[ab] >> 1);
-                let not_col_down = !(opaque_mask[ab] << 1);
-                self.face_masks[up_faces] = unpadded_opaque_col & not_front_col;
-                self.face_masks[down_faces] = unpadded_opaque_col & not_back_col;
-
-                self.face_masks[right_faces] = unpadded_opaque_col & not_right_col;
-                self.face_masks[left_faces] = unpadded_opaque_col & not_left_col;
-
-                self.face_masks[front_faces] = opaque_col & not_col_up;
-                self.face_masks[back_faces] = opaque_col & not_col_down;
-
-                // check if there's transparent blocks in this column
-                let mut bits_here = trans_mask[ab] & Self::P_MASK;
-                if bits_here == 0 {
-                    continue;
-                }
-                // Block-wise transparent step
-                // The transparent step is slower than the opaque step
-                // because we need to check if neighboring transparent blocks are differents (we don't care about that for opaque blocks)
-                let ab_ = ab * Self::CS_P;
-                while bits_here != 0 {
-                    let c = bits_here.trailing_zeros() as usize;
-                    let c_mask = 1 << c;
-                    let unpadded_c_mask = c_mask >> 1;
-                    bits_here &= !(c_mask);
-                    let abc = ab_ + c;
+        // The columns in the x or y layers are culled whole, for the faces of every axis
+        for a in 1..(Self::CY_P - 1) {
+            let bs = if ys.contains(&(a - 1)) {
+                1..(Self::CX_P - 1)
+            } else {
+                (xs.start + 1)..(xs.end + 1)
+            };
+            for b in bs {
+                self.fast_cull_column::<V, true>(voxels, opaque_mask, trans_mask, a, b, p_mask);
+            }
+        }
+        // The other columns only need their z layers, for the front and back faces
+        let z_mask = Self::z_mask(&layers[2]) & p_mask;
+        if z_mask == M::ZERO {
+            return;
+        }
+        for a in (1..(ys.start + 1)).chain((ys.end + 1)..(Self::CY_P - 1)) {
+            for b in (1..(xs.start + 1)).chain((xs.end + 1)..(Self::CX_P - 1)) {
+                self.fast_cull_column::<V, false>(voxels, opaque_mask, trans_mask, a, b, z_mask);
+            }
+        }
+    }
+
+    /// Culls the faces of the padded column b;a in the bits of `layers_mask`,
+    /// only the front and back faces are computed without `SIDES`
+    #[inline(always)]
+    fn fast_cull_column<V: Voxel, const SIDES: bool>(
+        &mut self,
+        voxels: &[V],
+        opaque_mask: &[M],
+        trans_mask: &[M],
+        a: usize,
+        b: usize,
+        layers_mask: M,
+    ) {
+        // Column-wise opaque step
+        let ab = a * Self::CX_P + b;
+        let opaque_col = opaque_mask[ab] & layers_mask;
+        let ba_index = (b - 1) + (a - 1) * CX;
+        let ab_index = (a - 1) + (b - 1) * CY;
+        let up_faces = ba_index;
+        let down_faces = ba_index + Self::FACE_LEN;
+        let right_faces = ab_index + 2 * Self::FACE_LEN;
+        let left_faces = ab_index + 3 * Self::FACE_LEN;
+        let front_faces = ba_index + 4 * Self::FACE_LEN;
+        let back_faces = ba_index + 5 * Self::FACE_LEN;
+        let not_col_up = !(opaque_mask[ab] >> 1);
+        let not_col_down = !(opaque_mask[ab] << 1);
+        // The neighbouring columns are only read for the up, down, right and left faces
+        let [not_front_col, not_back_col, not_right_col, not_left_col] = if SIDES {
+            [ab + Self::CX_P, ab - Self::CX_P, ab + 1, ab - 1].map(|col| !opaque_mask[col] >> 1)
+        } else {
+            [M::ZERO; 4]
+        };
+        if SIDES {
+            let unpadded_opaque_col = opaque_col >> 1;
+            self.face_masks[up_faces] = unpadded_opaque_col & not_front_col;
+            self.face_masks[down_faces] = unpadded_opaque_col & not_back_col;
+
+            self.face_masks[right_faces] = unpadded_opaque_col & not_right_col;
+            self.face_masks[left_faces] = unpadded_opaque_col & not_left_col;
+        }
+
+        self.face_masks[front_faces] = opaque_col & not_col_up;
+        self.face_masks[back_faces] = opaque_col & not_col_down;
+
+        // check if there's transparent blocks in this column
+        let mut bits_here = trans_mask[ab] & layers_mask;
+        if bits_here == M::ZERO {
+            return;
+        }
+        // Block-wise transparent step
+        // The transparent step is slower than the opaque step
+        // because we need to check if neighboring transparent blocks are differents (we don't care about that for opaque blocks)
+        let ab_ = Self::padded_index(b, a, 0);
+        while bits_here != M::ZERO {
+            let c = bits_here.trailing_zeros();
+            let c_mask = M::ONE << c;
+            let unpadded_c_mask = c_mask >> 1;
+            bits_here &= !(c_mask);
+            let abc = ab_ + c * Self::STRIDE_Z;
+            let v1 = voxels[abc];
+            if SIDES {
+                self.face_masks[up_faces] |= not_front_col
+                    & unpadded_c_mask
+                    & M::from_bool(v1 != voxels[abc + Self::STRIDE_Y]) << (c - 1);
+                self.face_masks[down_faces] |= not_back_col
+                    & unpadded_c_mask
+                    & M::from_bool(v1 != voxels[abc - Self::STRIDE_Y]) << (c - 1);
+
+                self.face_masks[right_faces] |= not_right_col
+                    & unpadded_c_mask
+                    & M::from_bool(v1 != voxels[abc + Self::STRIDE_X]) << (c - 1);
+                self.face_masks[left_faces] |= not_left_col
+                    & unpadded_c_mask
+                    & M::from_bool(v1 != voxels[abc - Self::STRIDE_X]) << (c - 1);
+            }
+
+            self.face_masks[front_faces] |=
+                not_col_up & c_mask & M::from_bool(v1 != voxels[abc + Self::STRIDE_Z]) << c;
+            self.face_masks[back_faces] |=
+                not_col_down & c_mask & M::from_bool(v1 != voxels[abc - Self::STRIDE_Z]) << c;
+        }
+    }
+
+    /// Same as face_culling but reading an unpadded voxel buffer, the voxels across the chunk's borders
+    /// are read from the neighbours' border layers (see [`ChunkMesher::mesh_unpadded`])
+    fn face_culling_unpadded<V: Voxel>(
+        &mut self,
+        voxels: &[V],
+        neighbours: [&[V]; 6],
+        classifier: &impl VoxelClassifier<V>,
+    ) {
+        const UP: usize = Face::Up as usize;
+        const DOWN: usize = Face::Down as usize;
+        const RIGHT: usize = Face::Right as usize;
+        const LEFT: usize = Face::Left as usize;
+        const FRONT: usize = Face::Front as usize;
+        const BACK: usize = Face::Back as usize;
+        assert_eq!(voxels.len(), CX * CY * CZ);
+        assert_eq!(neighbours[UP].len(), CX * CZ);
+        assert_eq!(neighbours[DOWN].len(), CX * CZ);
+        assert_eq!(neighbours[RIGHT].len(), CY * CZ);
+        assert_eq!(neighbours[LEFT].len(), CY * CZ);
+        assert_eq!(neighbours[FRONT].len(), CX * CY);
+        assert_eq!(neighbours[BACK].len(), CX * CY);
+        let [sx, sy, sz] = Self::UNPADDED_STRIDES;
+        for y in 0..CY {
+            for x in 0..CX {
+                let start = x * sx + y * sy;
+                // The neighbouring columns as (buffer, start, stride), from this chunk or from the neighbours' border layers
+                let up_col = if y + 1 < CY {
+                    (voxels, start + sy, sz)
+                } else {
+                    (neighbours[UP], x * CZ, 1)
+                };
+                let down_col = if y > 0 {
+                    (voxels, start - sy, sz)
+                } else {
+                    (neighbours[DOWN], x * CZ, 1)
+                };
+                let right_col = if x + 1 < CX {
+                    (voxels, start + sx, sz)
+                } else {
+                    (neighbours[RIGHT], y * CZ, 1)
+                };
+                let left_col = if x > 0 {
+                    (voxels, start - sx, sz)
+                } else {
+                    (neighbours[LEFT], y * CZ, 1)
+                };
+                let at =
+                    |(col, start, stride): (&[V], usize, usize), z: usize| col[start + z * stride];
+                let front = neighbours[FRONT][x + y * CX];
+                let back = neighbours[BACK][x + y * CX];
+                let ba_index = x + y * CX;
+                let ab_index = y + x * CY;
+                let up_faces = ba_index;
+                let down_faces = ba_index + Self::FACE_LEN;
+                let right_faces = ab_index + 2 * Self::FACE_LEN;
+                let left_faces = ab_index + 3 * Self::FACE_LEN;
+                let front_faces = ba_index + 4 * Self::FACE_LEN;
+                let back_faces = ba_index + 5 * Self::FACE_LEN;
+
+                for z in 0..CZ {
+                    let abc = start + z * sz;
                     let v1 = voxels[abc];
-                    self.face_masks[up_faces] |= not_front_col
-                        & unpadded_c_mask
-                        & ((v1 != voxels[abc + Self::CS_P2]) as u64) << (c - 1);
-                    self.face_masks[down_faces] |= not_back_col
-                        & unpadded_c_mask
-                        & ((v1 != voxels[abc - Self::CS_P2]) as u64) << (c - 1);
-
-                    self.face_masks[right_faces] |= not_right_col
-                        & unpadded_c_mask
-                        & ((v1 != voxels[abc + Self::CS_P]) as u64) << (c - 1);
-                    self.face_masks[left_faces] |= not_left_col
-                        & unpadded_c_mask
-                        & ((v1 != voxels[abc - Self::CS_P]) as u64) << (c - 1);
+                    if classifier.classify(v1) == VoxelKind::Air {
+                        continue;
+                    }
+                    self.face_masks[up_faces] |=
+                        face_value::<M, V>(v1, at(up_col, z), classifier) << z;
+                    self.face_masks[down_faces] |=
+                        face_value::<M, V>(v1, at(down_col, z), classifier) << z;
+
+                    self.face_masks[right_faces] |=
+                        face_value::<M, V>(v1, at(right_col, z), classifier) << z;
+                    self.face_masks[left_faces] |=
+                        face_value::<M, V>(v1, at(left_col, z), classifier) << z;
 
+                    let v_front = if z + 1 < CZ { voxels[abc + sz] } else { front };
+                    let v_back = if z > 0 { voxels[abc - sz] } else { back };
                     self.face_masks[front_faces] |=
-                        not_col_up & c_mask & ((v1 != voxels[abc + 1]) as u64) << c;
+                        face_value::<M, V>(v1, v_front, classifier) << (z + 1);
                     self.face_masks[back_faces] |=
-                        not_col_down & c_mask & ((v1 != voxels[abc - 1]) as u64) << c;
+                        face_value::<M, V>(v1, v_back, classifier) << (z + 1);
                 }
             }
         }
     }
 
-    fn face_merging(&mut self, voxels: &[u16]) {
+    /// The index of the air cell the face of the voxel at `index` looks into,
+    /// and the strides of the 2 axes tangent to the face
+    #[inline]
+    fn face_air(face: usize, index: usize) -> (usize, usize, usize) {
+        match face {
+            0 => (index + Self::STRIDE_Y, Self::STRIDE_X, Self::STRIDE_Z),
+            1 => (index - Self::STRIDE_Y, Self::STRIDE_X, Self::STRIDE_Z),
+            2 => (index + Self::STRIDE_X, Self::STRIDE_Y, Self::STRIDE_Z),
+            3 => (index - Self::STRIDE_X, Self::STRIDE_Y, Self::STRIDE_Z),
+            4 => (index + Self::STRIDE_Z, Self::STRIDE_X, Self::STRIDE_Y),
+            _ => (index - Self::STRIDE_Z, Self::STRIDE_X, Self::STRIDE_Y),
+        }
+    }
+
+    /// Computes the AO of the face of the voxel at `index`, packed as 4 * 2 bits
+    /// in the order of [`Face::vertices_packed`], `occludes` tells if the voxel at an index casts occlusion
+    #[inline]
+    fn face_ao(face: usize, index: usize, occludes: &impl Fn(usize) -> bool) -> u8 {
+        let (air, t1, t2) = Self::face_air(face, index);
+        let sides1 = [occludes(air - t1), occludes(air + t1)];
+        let sides2 = [occludes(air - t2), occludes(air + t2)];
+        let mut ao = 0;
+        for (i, &corner) in VERTEX_CORNERS[face].iter().enumerate() {
+            let (s1, s2) = (corner >> 1, corner & 1);
+            let side1 = sides1[s1];
+            let side2 = sides2[s2];
+            let corner_voxel = air + s1 * 2 * t1 + s2 * 2 * t2 - t1 - t2;
+            let occlusion = if side1 && side2 {
+                3
+            } else {
+                side1 as u8 + side2 as u8 + occludes(corner_voxel) as u8
+            };
+            ao |= occlusion << (2 * i);
+        }
+        ao
+    }
+
+    /// The attributes of the face of the voxel at `index`, faces only merge if their keys match:
+    /// AO in the 1st byte, light in the 2nd byte and the smooth light of the 4 vertices in the next 4 bytes
+    #[inline]
+    fn face_key(
+        face: usize,
+        index: usize,
+        attributes: &FaceAttributes,
+        occludes: &impl Fn(usize) -> bool,
+    ) -> u64 {
+        let ao = if attributes.ao {
+            Self::face_ao(face, index, occludes)
+        } else {
+            0
+        };
+        let light = match attributes.light {
+            Some(light) => light[Self::face_air(face, index).0],
+            None => 0,
+        };
+        let smooth_light = match attributes.smooth_light {
+            Some(light) => Self::face_smooth_light(face, index, light),
+            None => 0,
+        };
+        ao as u64 | (light as u64) << 8 | (smooth_light as u64) << 16
+    }
+
+    /// Computes the smooth light of the 4 vertices of the face of the voxel at `index`, 1 byte each
+    /// in the order of [`Face::vertices_packed`], see [`Face::vertices_smooth_light`]
+    #[inline]
+    fn face_smooth_light(face: usize, index: usize, light: &[u8]) -> u32 {
+        let (air, t1, t2) = Self::face_air(face, index);
+        let mut res = 0;
+        for (i, &corner) in VERTEX_CORNERS[face].iter().enumerate() {
+            let (s1, s2) = (corner >> 1, corner & 1);
+            // the 4 cells around the vertex, in front of the face
+            let side1 = air + s1 * 2 * t1 - t1;
+            let side2 = air + s2 * 2 * t2 - t2;
+            let corner_cell = side1 + s2 * 2 * t2 - t2;
+            let sum = light[air] as u32
+                + light[side1] as u32
+                + light[side2] as u32
+                + light[corner_cell] as u32;
+            res |= (sum / 4) << (8 * i);
+        }
+        res
+    }
+
+    /// With `ATTRS`, also computes the face attributes (using `occludes` to tell which voxels cast occlusion),
+    /// and only merges faces with the same attributes.
+    /// Without `PADDED`, `voxels` is an unpadded buffer, which only works without attributes.
+    /// Only the given layers (on the x, y and z axes) are meshed.
+    /// `transparent` tells which voxels are transparent, only used with `split_transparent`
+    fn face_merging<V: Voxel, const ATTRS: bool, const PADDED: bool>(
+        &mut self,
+        voxels: &[V],
+        attributes: &FaceAttributes,
+        occludes: impl Fn(usize) -> bool,
+        transparent: impl Fn(usize) -> bool,
+        merge: &impl MergePolicy<V>,
+        layers: &[Range<usize>; 3],
+    ) {
+        let split = self.split_transparent;
         // Greedy meshing faces 0-3
         for face in 0..=3 {
             let axis = face / 2;
+            // faces 0-1 are laid out as x + y * CX, faces 2-3 as y + x * CY
+            let (layers, forwards) = if axis == 0 {
+                (layers[1].clone(), CX)
+            } else {
+                (layers[0].clone(), CY)
+            };
 
-            for layer in 0..CS {
-                let bits_location = layer * CS + face * Self::CS_2;
+            for layer in layers {
+                let bits_location = layer * forwards + face * Self::FACE_LEN;
 
-                for forward in 0..CS {
+                for forward in 0..forwards {
                     let mut bits_here = self.face_masks[forward + bits_location];
-                    if bits_here == 0 {
+                    if bits_here == M::ZERO {
                         continue;
                     }
 
-                    let bits_next = if forward + 1 < CS {
+                    let bits_next = if forward + 1 < forwards {
                         self.face_masks[(forward + 1) + bits_location]
                     } else {
-                        0
+                        M::ZERO
                     };
-
                     let mut right_merged = 1;
-                    while bits_here != 0 {
-                        let bit_pos = bits_here.trailing_zeros() as usize;
-
-                        let v_type =
-                            voxels[get_axis_index::<CS>(axis, forward + 1, bit_pos + 1, layer + 1)];
-
-                        if (bits_next >> bit_pos & 1) != 0
-                            && v_type
-                                == voxels[get_axis_index::<CS>(
-                                    axis,
-                                    forward + 2,
-                                    bit_pos + 1,
-                                    layer + 1,
-                                )]
+                    while bits_here != M::ZERO {
+                        let bit_pos = bits_here.trailing_zeros();
+
+                        let index = Self::get_axis_index::<PADDED>(
+                            axis,
+                            forward + 1,
+                            bit_pos + 1,
+                            layer + 1,
+                        );
+                        let v_type = voxels[index];
+                        let merge_key = merge.merge_key(v_type);
+                        let key = if ATTRS {
+                            Self::face_key(face, index, attributes, &occludes)
+                        } else {
+                            0
+                        };
+
+                        let is_transparent = split && transparent(index);
+
+                        let next_index = Self::get_axis_index::<PADDED>(
+                            axis,
+                            forward + 2,
+                            bit_pos + 1,
+                            layer + 1,
+                        );
+                        if (bits_next >> bit_pos & M::ONE) != M::ZERO
+                            && merge_key.is_some()
+                            && merge_key == merge.merge_key(voxels[next_index])
+                            && (!ATTRS
+                                || key == Self::face_key(face, next_index, attributes, &occludes))
+                            && (!split || is_transparent == transparent(next_index))
                         {
                             self.forward_merged[bit_pos] += 1;
-                            bits_here &= !(1 << bit_pos);
+                            bits_here &= !(M::ONE << bit_pos);
                             continue;
                         }
 
-                        for right in (bit_pos + 1)..CS {
-                            if (bits_here >> right & 1) == 0
+                        for right in (bit_pos + 1)..CZ {
+                            let right_index = Self::get_axis_index::<PADDED>(
+                                axis,
+                                forward + 1,
+                                right + 1,
+                                layer + 1,
+                            );
+                            if (bits_here >> right & M::ONE) == M::ZERO
                                 || self.forward_merged[bit_pos] != self.forward_merged[right]
-                                || v_type
-                                    != voxels[get_axis_index::<CS>(
-                                        axis,
-                                        forward + 1,
-                                        right + 1,
-                                        layer + 1,
-                                    )]
+                                || merge_key.is_none()
+                                || merge_key != merge.merge_key(voxels[right_index])
+                                || (ATTRS
+                                    && key
+                                        != Self::face_key(face, right_index, attributes, &occludes))
+                                || (split && is_transparent != transparent(right_index))
                             {
                                 break;
                             }
                             self.forward_merged[right] = 0;
                             right_merged += 1;
                         }
-                        bits_here &= !((1 << (bit_pos + right_merged)) - 1);
+                        bits_here =
+                            bits_here >> (bit_pos + right_merged) << (bit_pos + right_merged);
 
                         let mesh_front = forward - self.forward_merged[bit_pos] as usize;
                         let mesh_left = bit_pos;
                         let mesh_up = layer + (!face & 1);
 
                         let mesh_width = right_merged;
-                        let mesh_length = (self.forward_merged[bit_pos] + 1) as usize;
+                        let mesh_length = self.forward_merged[bit_pos] as usize + 1;
 
                         self.forward_merged[bit_pos] = 0;
                         right_merged = 1;
 
-                        let v_type = v_type as usize;
+                        let v_type = v_type.quad_id() as usize;
 
                         let quad = match face {
                             0 => Quad::pack(
@@ -239,7 +802,7 @@ impl<const CS: usize> Mesher<CS> {
                                 v_type,
                             ),
                             1 => Quad::pack(
-                                mesh_front + mesh_length as usize,
+                                mesh_front + mesh_length,
                                 mesh_up,
                                 mesh_left,
                                 mesh_length,
@@ -248,7 +811,7 @@ impl<const CS: usize> Mesher<CS> {
                             ),
                             2 => Quad::pack(
                                 mesh_up,
-                                mesh_front + mesh_length as usize,
+                                mesh_front + mesh_length,
                                 mesh_left,
                                 mesh_length,
                                 mesh_width,
@@ -264,64 +827,87 @@ impl<const CS: usize> Mesher<CS> {
                             ),
                             _ => unreachable!(),
                         };
-                        self.quads[face].push(quad);
+                        let quad = if ATTRS {
+                            attributes.apply(quad, key)
+                        } else {
+                            quad
+                        };
+                        self.push_quad(face, quad, is_transparent);
                     }
                 }
             }
         }
 
         // Greedy meshing faces 4-5
+        let z_mask = Self::z_mask(&layers[2]);
         for face in 4..6 {
             let axis = face / 2;
 
-            for forward in 0..CS {
-                let bits_location = forward * CS + face * Self::CS_2;
-                let bits_forward_location = (forward + 1) * CS + face * Self::CS_2;
+            for forward in 0..CY {
+                let bits_location = forward * CX + face * Self::FACE_LEN;
+                let bits_forward_location = (forward + 1) * CX + face * Self::FACE_LEN;
 
-                for right in 0..CS {
-                    let mut bits_here = self.face_masks[right + bits_location];
-                    if bits_here == 0 {
+                for right in 0..CX {
+                    let mut bits_here = self.face_masks[right + bits_location] & z_mask;
+                    if bits_here == M::ZERO {
                         continue;
                     }
 
-                    let bits_forward = if forward < CS - 1 {
+                    let bits_forward = if forward < CY - 1 {
                         self.face_masks[right + bits_forward_location]
                     } else {
-                        0
+                        M::ZERO
                     };
-                    let bits_right = if right < CS - 1 {
+                    let bits_right = if right < CX - 1 {
                         self.face_masks[right + 1 + bits_location]
                     } else {
-                        0
+                        M::ZERO
                     };
-                    let right_cs = right * CS;
+                    let right_cz = right * CZ;
 
-                    while bits_here != 0 {
-                        let bit_pos = bits_here.trailing_zeros() as usize;
+                    while bits_here != M::ZERO {
+                        let bit_pos = bits_here.trailing_zeros();
 
-                        bits_here &= !(1 << bit_pos);
+                        bits_here &= !(M::ONE << bit_pos);
 
-                        let v_type =
-                            voxels[get_axis_index::<CS>(axis, right + 1, forward + 1, bit_pos)];
-                        let forward_merge_i = right_cs + (bit_pos - 1);
+                        let index =
+                            Self::get_axis_index::<PADDED>(axis, right + 1, forward + 1, bit_pos);
+                        let v_type = voxels[index];
+                        let merge_key = merge.merge_key(v_type);
+                        let key = if ATTRS {
+                            Self::face_key(face, index, attributes, &occludes)
+                        } else {
+                            0
+                        };
+                        let is_transparent = split && transparent(index);
+                        let forward_merge_i = right_cz + (bit_pos - 1);
                         let right_merged_ref = &mut self.right_merged[bit_pos - 1];
 
+                        let forward_index =
+                            Self::get_axis_index::<PADDED>(axis, right + 1, forward + 2, bit_pos);
                         if *right_merged_ref == 0
-                            && (bits_forward >> bit_pos & 1) != 0
-                            && v_type
-                                == voxels
-                                    [get_axis_index::<CS>(axis, right + 1, forward + 2, bit_pos)]
+                            && (bits_forward >> bit_pos & M::ONE) != M::ZERO
+                            && merge_key.is_some()
+                            && merge_key == merge.merge_key(voxels[forward_index])
+                            && (!ATTRS
+                                || key
+                                    == Self::face_key(face, forward_index, attributes, &occludes))
+                            && (!split || is_transparent == transparent(forward_index))
                         {
                             self.forward_merged[forward_merge_i] += 1;
                             continue;
                         }
 
-                        if (bits_right >> bit_pos & 1) != 0
+                        let right_index =
+                            Self::get_axis_index::<PADDED>(axis, right + 2, forward + 1, bit_pos);
+                        if (bits_right >> bit_pos & M::ONE) != M::ZERO
                             && self.forward_merged[forward_merge_i]
-                                == self.forward_merged[(right_cs + CS) + (bit_pos - 1)]
-                            && v_type
-                                == voxels
-                                    [get_axis_index::<CS>(axis, right + 2, forward + 1, bit_pos)]
+                                == self.forward_merged[(right_cz + CZ) + (bit_pos - 1)]
+                            && merge_key.is_some()
+                            && merge_key == merge.merge_key(voxels[right_index])
+                            && (!ATTRS
+                                || key == Self::face_key(face, right_index, attributes, &occludes))
+                            && (!split || is_transparent == transparent(right_index))
                         {
                             self.forward_merged[forward_merge_i] = 0;
                             *right_merged_ref += 1;
@@ -332,121 +918,420 @@ impl<const CS: usize> Mesher<CS> {
                         let mesh_front = forward - self.forward_merged[forward_merge_i] as usize;
                         let mesh_up = bit_pos - 1 + (!face & 1);
 
-                        let mesh_width = 1 + *right_merged_ref;
-                        let mesh_length = 1 + self.forward_merged[forward_merge_i];
+                        let mesh_width = 1 + *right_merged_ref as usize;
+                        let mesh_length = 1 + self.forward_merged[forward_merge_i] as usize;
 
                         self.forward_merged[forward_merge_i] = 0;
                         *right_merged_ref = 0;
 
                         let quad = Quad::pack(
-                            mesh_left + (if face == 4 { mesh_width } else { 0 }) as usize,
+                            mesh_left + if face == 4 { mesh_width } else { 0 },
                             mesh_front,
                             mesh_up,
-                            mesh_width as usize,
-                            mesh_length as usize,
-                            v_type as usize,
+                            mesh_width,
+                            mesh_length,
+                            v_type.quad_id() as usize,
                         );
-                        self.quads[face].push(quad);
+                        let quad = if ATTRS {
+                            attributes.apply(quad, key)
+                        } else {
+                            quad
+                        };
+                        self.push_quad(face, quad, is_transparent);
                     }
                 }
             }
         }
     }
 
-    /// Meshes a voxel buffer representing a chunk, using an opaque and transparent mask with 1 u64 per column with 1 bit per voxel in the column,
+    /// Outputs a quad and grows the bounds of its group
+    #[inline]
+    fn push_quad(&mut self, face: usize, quad: Quad<CX, CY, CZ>, transparent: bool) {
+        self.grow_bounds(face, quad, transparent);
+        if transparent {
+            self.transparent_quads[face].push(quad);
+        } else {
+            self.quads[face].push(quad);
+        }
+    }
+
+    #[inline]
+    fn grow_bounds(&mut self, face: usize, quad: Quad<CX, CY, CZ>, transparent: bool) {
+        let (min, max) = Face::from(face as u8).quad_extents(quad);
+        let bounds = &mut self.bounds[transparent as usize][face];
+        for i in 0..3 {
+            bounds.0[i] = bounds.0[i].min(min[i]);
+            bounds.1[i] = bounds.1[i].max(max[i]);
+        }
+    }
+
+    /// The bounding box (in chunk space) of the quads of a face in `quads`, computed while meshing,
+    /// it can be used with [`Face::is_visible`] to skip the whole group
+    pub fn face_bounds(&self, face: Face) -> Aabb {
+        Aabb::from_extents(self.bounds[0][face as usize])
+    }
+
+    /// The bounding box (in chunk space) of the quads of a face in `transparent_quads`
+    pub fn transparent_face_bounds(&self, face: Face) -> Aabb {
+        Aabb::from_extents(self.bounds[1][face as usize])
+    }
+
+    /// The bounding box (in chunk space) of the whole output, `quads` and `transparent_quads` included,
+    /// empty if there are no quads
+    pub fn bounds(&self) -> Aabb {
+        let mut bounds = Self::EMPTY_BOUNDS;
+        for (min, max) in self.bounds.iter().flatten() {
+            for i in 0..3 {
+                bounds.0[i] = bounds.0[i].min(min[i]);
+                bounds.1[i] = bounds.1[i].max(max[i]);
+            }
+        }
+        Aabb::from_extents(bounds)
+    }
+
+    const EMPTY_BOUNDS: ([u32; 3], [u32; 3]) = ([u32::MAX; 3], [0; 3]);
+
+    /// Meshes a voxel buffer representing a chunk, using an opaque and transparent mask with 1 `M` per column with 1 bit per voxel in the column,
     /// signaling if the voxel is opaque or transparent.
     /// This is ~4x faster than the regular mesh method but requires maintaining 2 masks for each chunk.
     /// See https://github.com/Inspirateur/binary-greedy-meshing?tab=readme-ov-file#what-to-do-with-mesh_dataquads for using the output
-    pub fn fast_mesh(&mut self, voxels: &[u16], opaque_mask: &[u64], trans_mask: &[u64]) {
-        self.fast_face_culling(voxels, opaque_mask, trans_mask);
-        self.face_merging(voxels);
+    pub fn fast_mesh<V: Voxel>(&mut self, voxels: &[V], opaque_mask: &[M], trans_mask: &[M]) {
+        self.fast_face_culling(voxels, opaque_mask, trans_mask, &Self::ALL_LAYERS);
+        self.face_merging::<V, false, true>(
+            voxels,
+            &FaceAttributes::default(),
+            |_| false,
+            |i| Self::mask_bit(trans_mask, i),
+            &MergeSameId,
+            &Self::ALL_LAYERS,
+        );
+    }
+
+    /// Same as fast_mesh, but also computes the ambient occlusion of each quad's vertices from the opaque mask
+    /// (see [`Quad::ao`]), faces are only merged if their AO values match.
+    pub fn fast_mesh_ao<V: Voxel>(&mut self, voxels: &[V], opaque_mask: &[M], trans_mask: &[M]) {
+        let attributes = FaceAttributes {
+            ao: true,
+            ..Default::default()
+        };
+        self.fast_mesh_with(voxels, opaque_mask, trans_mask, &attributes, &MergeSameId);
+    }
+
+    /// Same as fast_mesh, but also computes the given face attributes (AO from the opaque mask and/or light),
+    /// faces are only merged if their attributes match and `merge` allows it (see [`MergePolicy`]).
+    pub fn fast_mesh_with<V: Voxel>(
+        &mut self,
+        voxels: &[V],
+        opaque_mask: &[M],
+        trans_mask: &[M],
+        attributes: &FaceAttributes,
+        merge: &impl MergePolicy<V>,
+    ) {
+        self.fast_face_culling(voxels, opaque_mask, trans_mask, &Self::ALL_LAYERS);
+        let occludes = |i: usize| Self::mask_bit(opaque_mask, i);
+        let transparent = |i: usize| Self::mask_bit(trans_mask, i);
+        if attributes.is_empty() {
+            self.face_merging::<V, false, true>(
+                voxels,
+                attributes,
+                occludes,
+                transparent,
+                merge,
+                &Self::ALL_LAYERS,
+            );
+        } else {
+            self.face_merging::<V, true, true>(
+                voxels,
+                attributes,
+                occludes,
+                transparent,
+                merge,
+                &Self::ALL_LAYERS,
+            );
+        }
+    }
+
+    /// Updates the quads of a chunk after the voxels with min <= x;y;z < max (in 0..CX;0..CY;0..CZ) were edited,
+    /// `self.quads` (and `self.transparent_quads`) must hold the chunk's previous output of fast_mesh (so don't call clear before this).
+    /// Only the layers of each face that the edit can affect are culled and merged again,
+    /// the previous quads in those layers are replaced and the others are kept, new quads are appended.
+    pub fn fast_remesh<V: Voxel>(
+        &mut self,
+        voxels: &[V],
+        opaque_mask: &[M],
+        trans_mask: &[M],
+        min: [usize; 3],
+        max: [usize; 3],
+    ) {
+        self.fast_remesh_with(
+            voxels,
+            opaque_mask,
+            trans_mask,
+            min,
+            max,
+            &FaceAttributes::default(),
+            &MergeSameId,
+        );
     }
 
-    /// Meshes a voxel buffer representing a chunk, using a BTreeSet signaling which voxel values are transparent.
-    /// This is ~4x slower than the fast_mesh method but does not require maintaining 2 masks for each chunk.
+    /// Same as fast_remesh for quads meshed with fast_mesh_with, `attributes` and `merge` must be the same as in that call.
+    /// If the edit changed the light buffers, min and max must also cover the voxels whose light changed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fast_remesh_with<V: Voxel>(
+        &mut self,
+        voxels: &[V],
+        opaque_mask: &[M],
+        trans_mask: &[M],
+        min: [usize; 3],
+        max: [usize; 3],
+        attributes: &FaceAttributes,
+        merge: &impl MergePolicy<V>,
+    ) {
+        let dims = [CX, CY, CZ];
+        assert!((0..3).all(|i| max[i] <= dims[i]), "region out of the chunk");
+        if (0..3).any(|i| min[i] >= max[i]) {
+            return;
+        }
+        // An edited voxel changes the faces of its neighbours, and their AO and light
+        let layers: [Range<usize>; 3] =
+            core::array::from_fn(|i| min[i].saturating_sub(1)..(max[i] + 1).min(dims[i]));
+        for face in 0..6 {
+            // The coordinate of the quads on the face's axis is their layer, + 1 for the faces on the + side
+            let axis = [1, 0, 2][face / 2];
+            let offset = (face & 1 == 0) as u64;
+            let kept = |quad: &Quad<CX, CY, CZ>| {
+                !layers[axis].contains(&((quad.xyz()[axis] - offset) as usize))
+            };
+            self.quads[face].retain(kept);
+            self.transparent_quads[face].retain(kept);
+        }
+        // The bounds of the kept quads
+        self.bounds = [[Self::EMPTY_BOUNDS; 6]; 2];
+        for face in 0..6 {
+            for i in 0..self.quads[face].len() {
+                self.grow_bounds(face, self.quads[face][i], false);
+            }
+            for i in 0..self.transparent_quads[face].len() {
+                self.grow_bounds(face, self.transparent_quads[face][i], true);
+            }
+        }
+        self.fast_face_culling(voxels, opaque_mask, trans_mask, &layers);
+        let occludes = |i: usize| Self::mask_bit(opaque_mask, i);
+        let transparent = |i: usize| Self::mask_bit(trans_mask, i);
+        if attributes.is_empty() {
+            self.face_merging::<V, false, true>(
+                voxels,
+                attributes,
+                occludes,
+                transparent,
+                merge,
+                &layers,
+            );
+        } else {
+            self.face_merging::<V, true, true>(
+                voxels,
+                attributes,
+                occludes,
+                transparent,
+                merge,
+                &layers,
+            );
+        }
+    }
+
+    /// Meshes a voxel buffer representing a chunk, using a classifier signaling which voxel values are transparent,
+    /// a [`TransparentSet`] is the fastest option but a `BTreeSet` of the transparent values also works.
+    /// This is slower than the fast_mesh method but does not require maintaining 2 masks for each chunk.
     /// See https://github.com/Inspirateur/binary-greedy-meshing?tab=readme-ov-file#what-to-do-with-mesh_dataquads for using the output
-    pub fn mesh(&mut self, voxels: &[u16], transparents: &BTreeSet<u16>) {
-        self.face_culling(voxels, transparents);
-        self.face_merging(voxels);
+    pub fn mesh<V: Voxel>(&mut self, voxels: &[V], classifier: &impl VoxelClassifier<V>) {
+        self.face_culling(voxels, classifier);
+        self.face_merging::<V, false, true>(
+            voxels,
+            &FaceAttributes::default(),
+            |_| false,
+            |i| classifier.classify(voxels[i]) == VoxelKind::Transparent,
+            &MergeSameId,
+            &Self::ALL_LAYERS,
+        );
+    }
+
+    /// Same as mesh, but also computes the ambient occlusion of each quad's vertices from the opaque voxels
+    /// (see [`Quad::ao`]), faces are only merged if their AO values match.
+    pub fn mesh_ao<V: Voxel>(&mut self, voxels: &[V], classifier: &impl VoxelClassifier<V>) {
+        let attributes = FaceAttributes {
+            ao: true,
+            ..Default::default()
+        };
+        self.mesh_with(voxels, classifier, &attributes, &MergeSameId);
+    }
+
+    /// Same as mesh, but also computes the given face attributes (AO from the opaque voxels and/or light),
+    /// faces are only merged if their attributes match and `merge` allows it (see [`MergePolicy`]).
+    pub fn mesh_with<V: Voxel>(
+        &mut self,
+        voxels: &[V],
+        classifier: &impl VoxelClassifier<V>,
+        attributes: &FaceAttributes,
+        merge: &impl MergePolicy<V>,
+    ) {
+        self.face_culling(voxels, classifier);
+        let occludes = |i: usize| classifier.classify(voxels[i]) == VoxelKind::Opaque;
+        let transparent = |i: usize| classifier.classify(voxels[i]) == VoxelKind::Transparent;
+        if attributes.is_empty() {
+            self.face_merging::<V, false, true>(
+                voxels,
+                attributes,
+                occludes,
+                transparent,
+                merge,
+                &Self::ALL_LAYERS,
+            );
+        } else {
+            self.face_merging::<V, true, true>(
+                voxels,
+                attributes,
+                occludes,
+                transparent,
+                merge,
+                &Self::ALL_LAYERS,
+            );
+        }
+    }
+
+    /// Same as mesh, but reads an unpadded chunk of CX*CY*CZ voxels (see [`ChunkMesher::linearize`])
+    /// so it doesn't need to be copied into a padded buffer.
+    /// Face culling only needs the voxels right across the chunk's faces,
+    /// `neighbours` holds the border layer of each face's neighbour, indexed by [`Face`]:
+    /// - Up and Down: the neighbour's layer touching this chunk (y = 0 for Up, y = CY-1 for Down), at index z + x*CZ
+    /// - Right and Left: the neighbour's layer touching this chunk, at index z + y*CZ
+    /// - Front and Back: the neighbour's layer touching this chunk, at index x + y*CX
+    ///
+    /// Pass a layer of air for a face to always be visible.
+    /// Since there's no padding, AO and light aren't available with this method.
+    ///
+    /// Like mesh, it classifies every voxel: it beats copying the chunk into a padded buffer and computing its masks
+    /// for each mesh, but it's about 2x slower than [`ChunkMesher::fast_mesh`] with masks kept up to date (see [`ChunkMasks`]),
+    /// which is the better choice for chunks that are meshed often
+    pub fn mesh_unpadded<V: Voxel>(
+        &mut self,
+        voxels: &[V],
+        neighbours: [&[V]; 6],
+        classifier: &impl VoxelClassifier<V>,
+    ) {
+        self.face_culling_unpadded(voxels, neighbours, classifier);
+        self.face_merging::<V, false, false>(
+            voxels,
+            &FaceAttributes::default(),
+            |_| false,
+            |i| classifier.classify(voxels[i]) == VoxelKind::Transparent,
+            &MergeSameId,
+            &Self::ALL_LAYERS,
+        );
     }
 }
 
-#[inline]
-/// v1 is not AIR
-fn face_value(v1: u16, v2: u16, transparents: &BTreeSet<u16>) -> u64 {
-    (v2 == 0 || (v1 != v2 && transparents.contains(&v2))) as u64
+impl<const CX: usize, const CY: usize, const CZ: usize, M: Mask, L: Layout> Default
+    for ChunkMesher<CX, CY, CZ, M, L>
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[inline]
-fn get_axis_index<const CS: usize>(axis: usize, a: usize, b: usize, c: usize) -> usize {
-    // TODO: figure out how to shuffle this around to make it work with YZX
-    match axis {
-        0 => b + (a * Mesher::<CS>::CS_P) + (c * Mesher::<CS>::CS_P2),
-        1 => b + (c * Mesher::<CS>::CS_P) + (a * Mesher::<CS>::CS_P2),
-        _ => c + (a * Mesher::<CS>::CS_P) + (b * Mesher::<CS>::CS_P2),
+/// Optional data computed for each face while meshing and stored in its quad,
+/// faces are only merged if all their attributes match.
+///
+/// AO and light each take 8 bits of the voxel id ([`Quad::ID_BITS`]), meshing a voxel whose id doesn't fit panics:
+/// with both, ids have 16 bits for CS <= 63 and 9 bits for 32x256x32 chunks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FaceAttributes<'a> {
+    /// Computes the ambient occlusion of each vertex (see [`Quad::ao`])
+    pub ao: bool,
+    /// A padded light buffer with the same layout as the voxel buffer,
+    /// the light of the air cell in front of each face is stored in its quad (see [`Quad::light`])
+    pub light: Option<&'a [u8]>,
+    /// A padded light buffer with the same layout as the voxel buffer,
+    /// faces are only merged if the smooth light of their 4 vertices match.
+    /// It isn't stored in the quads, use [`Face::vertices_smooth_light`] with the same buffer to get it
+    pub smooth_light: Option<&'a [u8]>,
+}
+
+impl FaceAttributes<'_> {
+    fn is_empty(&self) -> bool {
+        !self.ao && self.light.is_none() && self.smooth_light.is_none()
+    }
+
+    /// Stores a face key (as computed by `face_key`) in a quad
+    #[inline]
+    fn apply<const CX: usize, const CY: usize, const CZ: usize>(
+        &self,
+        quad: Quad<CX, CY, CZ>,
+        key: u64,
+    ) -> Quad<CX, CY, CZ> {
+        let quad = if self.ao {
+            quad.with_ao_bits(key as u8)
+        } else {
+            quad
+        };
+        if self.light.is_some() {
+            quad.with_light((key >> 8) as u8)
+        } else {
+            quad
+        }
     }
 }
 
-/// Compute Mesh indices for a given amount of quads
-pub fn indices(num_quads: usize) -> Vec<u32> {
-    // Each quads is made of 2 triangles which require 6 indices
-    // The indices are the same regardless of the face
-    let mut res = Vec::with_capacity(num_quads * 6);
-    for i in 0..num_quads as u32 {
-        res.push((i << 2) | 2);
-        res.push((i << 2) | 0);
-        res.push((i << 2) | 1);
-        res.push((i << 2) | 1);
-        res.push((i << 2) | 3);
-        res.push((i << 2) | 2);
-    }
-    res
+/// For each face, the corner of the face's air cell (2 * t1 + t2, with t1 and t2 as in `face_ao`,
+/// 1 meaning the + side) of each vertex of [`Face::vertices_packed`]
+const VERTEX_CORNERS: [[usize; 4]; 6] = [
+    [3, 2, 1, 0],
+    [1, 0, 3, 2],
+    [2, 3, 0, 1],
+    [0, 1, 2, 3],
+    [1, 0, 3, 2],
+    [0, 1, 2, 3],
+];
+
+#[inline]
+/// v1 is not AIR, so a neighbour with the same value hides the face without being classified
+fn face_value<M: Mask, V: Voxel>(v1: V, v2: V, classifier: &impl VoxelClassifier<V>) -> M {
+    M::from_bool(v1 != v2 && classifier.classify(v2) != VoxelKind::Opaque)
 }
 
-pub fn pad_linearize<const CS: usize>(x: usize, y: usize, z: usize) -> usize {
-    z + 1 + (x + 1) * Mesher::<CS>::CS_P + (y + 1) * Mesher::<CS>::CS_P2
+/// Index of the voxel x;y;z (in 0..CS) in a padded CS_P^3 voxel buffer
+pub fn pad_linearize<const CS: usize>(x: usize, y: usize, z: usize) -> usize
+where
+    Size<CS>: ChunkSize,
+{
+    Mesher::<CS>::pad_linearize(x, y, z)
 }
 
-/// Compute an opacity mask from a voxel buffer and a BTreeSet specifying which voxel values are transparent
-pub fn compute_opaque_mask<const CS: usize>(
-    voxels: &[u16],
-    transparents: &BTreeSet<u16>,
-) -> Box<[u64]> {
-    let mut opaque_mask = vec![0; Mesher::<CS>::CS_P2].into_boxed_slice();
-    // Fill the opacity mask
-    for (i, voxel) in voxels.iter().enumerate() {
-        // If the voxel is transparent we skip it
-        if *voxel == 0 || transparents.contains(voxel) {
-            continue;
-        }
-        let (r, q) = (i / Mesher::<CS>::CS_P, i % Mesher::<CS>::CS_P);
-        opaque_mask[r] |= 1 << q;
-    }
-    opaque_mask
+/// Compute an opacity mask from a voxel buffer and a classifier (such as a [`TransparentSet`])
+pub fn compute_opaque_mask<const CS: usize, V: Voxel>(
+    voxels: &[V],
+    classifier: &impl VoxelClassifier<V>,
+) -> Box<[MaskOf<CS>]>
+where
+    Size<CS>: ChunkSize,
+{
+    Mesher::<CS>::compute_opaque_mask(voxels, classifier)
 }
 
-/// Compute a transparent mask from a voxel buffer and a BTreeSet specifying which voxel values are transparent
-pub fn compute_transparent_mask<const CS: usize>(
-    voxels: &[u16],
-    transparents: &BTreeSet<u16>,
-) -> Box<[u64]> {
-    let mut trans_mask = vec![0; Mesher::<CS>::CS_P2].into_boxed_slice();
-    // Fill the opacity mask
-    for (i, voxel) in voxels.iter().enumerate() {
-        // If the voxel is opaque we skip it
-        if *voxel == 0 || !transparents.contains(voxel) {
-            continue;
-        }
-        let (r, q) = (i / Mesher::<CS>::CS_P, i % Mesher::<CS>::CS_P);
-        trans_mask[r] |= 1 << q;
-    }
-    trans_mask
+/// Compute a transparent mask from a voxel buffer and a classifier (such as a [`TransparentSet`])
+pub fn compute_transparent_mask<const CS: usize, V: Voxel>(
+    voxels: &[V],
+    classifier: &impl VoxelClassifier<V>,
+) -> Box<[MaskOf<CS>]>
+where
+    Size<CS>: ChunkSize,
+{
+    Mesher::<CS>::compute_transparent_mask(voxels, classifier)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{self as bgm};
-    use alloc::{boxed::Box, collections::btree_set::BTreeSet};
+    use alloc::{boxed::Box, collections::btree_set::BTreeSet, vec::Vec};
 
     pub const CS: usize = 62;
 
@@ -454,12 +1339,12 @@ mod tests {
     #[test]
     fn test_output() {
         extern crate std;
-        let mut voxels = [0; bgm::Mesher::<CS>::CS_P3];
+        let mut voxels = [0u16; bgm::Mesher::<CS>::CS_P3];
         voxels[bgm::pad_linearize::<CS>(0, 0, 0)] = 1;
         voxels[bgm::pad_linearize::<CS>(0, 1, 0)] = 1;
 
         let mut mesher = bgm::Mesher::<CS>::new();
-        let opaque_mask = bgm::compute_opaque_mask::<CS>(&voxels, &BTreeSet::new());
+        let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&voxels, &BTreeSet::new());
         let trans_mask = vec![0; bgm::Mesher::<CS>::CS_P2].into_boxed_slice();
         mesher.fast_mesh(&voxels, &opaque_mask, &trans_mask);
         // self.quads is the output
@@ -474,35 +1359,1599 @@ mod tests {
     /// Ensures that mesh and fast_mesh return the same results
     #[test]
     fn same_results() {
-        let voxels = test_buffer();
+        same_results_for::<CS>();
+    }
+
+    /// Same as same_results for the u32 and u128 mask widths
+    #[test]
+    fn same_results_all_widths() {
+        same_results_for::<30>();
+        same_results_for::<126>();
+    }
+
+    /// Ensures that the mask width doesn't change the output
+    #[test]
+    fn same_results_across_widths() {
+        const SMALL_CS: usize = 30;
+        let voxels = test_buffer::<SMALL_CS>();
+        let transparent_blocks = BTreeSet::from([2]);
+        let mut mesher1 = bgm::Mesher::<SMALL_CS>::new();
+        mesher1.mesh(&voxels, &transparent_blocks);
+        let mut mesher2 = bgm::Mesher::<SMALL_CS, u64>::new();
+        mesher2.mesh(&voxels, &transparent_blocks);
+        assert_eq!(mesher1.quads, mesher2.quads);
+    }
+
+    /// Ensures that quads from chunks bigger than 62 don't overflow their fields
+    #[test]
+    fn big_chunk_quads() {
+        const BIG_CS: usize = 126;
+        let mut voxels = vec![0u16; bgm::Mesher::<BIG_CS>::CS_P3];
+        for x in 0..BIG_CS {
+            voxels[bgm::pad_linearize::<BIG_CS>(x, BIG_CS - 1, BIG_CS - 1)] = 1;
+        }
+        let mut mesher = bgm::Mesher::<BIG_CS>::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+        let up = mesher.quads[bgm::Face::Up as usize][0];
+        assert_eq!(up.xyz(), [0, BIG_CS as u64, BIG_CS as u64 - 1]);
+        assert_eq!(up.width(), BIG_CS as u64);
+        assert_eq!(up.height(), 1);
+        assert_eq!(up.voxel_id(), 1);
+    }
+
+    /// Ensures that small chunks' denser quads give the same vertices
+    #[test]
+    fn small_chunk_quads() {
+        let small = bgm::Quad::<30>::pack(30, 1, 29, 30, 2, 3);
+        let regular = bgm::Quad::<62>::pack(30, 1, 29, 30, 2, 3);
+        assert_eq!(bgm::Quad::<30>::ID_SHIFT, 5 * 5 + 2);
+        assert_eq!(small.xyz(), regular.xyz());
+        assert_eq!(small.voxel_id(), regular.voxel_id());
+        for face in 0..6u8 {
+            let face = bgm::Face::from(face);
+            assert_eq!(
+                face.vertices_packed(small).map(|v| v.0),
+                face.vertices_packed(regular).map(|v| v.0)
+            );
+        }
+    }
+
+    /// Ensures that mesh and fast_mesh return the same results on tall chunks
+    #[test]
+    fn same_results_tall_chunk() {
+        type Tall = bgm::ChunkMesher<32, 256, 32>;
+        let voxels = chunk_buffer::<32, 256, 32>(|x, y, z| {
+            if y == 200 {
+                1
+            } else {
+                transparent_sphere::<32>(x, y % 32, z)
+            }
+        });
+        let transparent_blocks = BTreeSet::from([2]);
+        let opaque_mask = Tall::compute_opaque_mask(&voxels, &transparent_blocks);
+        let trans_mask = Tall::compute_transparent_mask(&voxels, &transparent_blocks);
+        let mut mesher1 = Tall::new();
+        mesher1.mesh(&voxels, &transparent_blocks);
+        let mut mesher2 = Tall::new();
+        mesher2.fast_mesh(&voxels, &opaque_mask, &trans_mask);
+        assert_eq!(mesher1.quads, mesher2.quads);
+    }
+
+    /// Ensures that a pillar spanning a 256 tall chunk is meshed into single quads
+    #[test]
+    fn tall_chunk_quads() {
+        type Tall = bgm::ChunkMesher<32, 256, 32>;
+        let voxels = chunk_buffer::<32, 256, 32>(|x, _y, z| (x == 0 && z == 0) as u16);
+        let mut mesher = Tall::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+        for quads in mesher.quads.iter() {
+            assert_eq!(quads.len(), 1);
+            assert_eq!(quads[0].voxel_id(), 1);
+        }
+        let right = mesher.quads[bgm::Face::Right as usize][0];
+        assert_eq!(right.xyz(), [1, 256, 0]);
+        assert_eq!((right.width(), right.height()), (256, 1));
+        let front = mesher.quads[bgm::Face::Front as usize][0];
+        assert_eq!(front.xyz(), [1, 0, 1]);
+        assert_eq!((front.width(), front.height()), (1, 256));
+    }
+
+    /// Ensures that a non-cubic chunk gives the same quads as a cubic chunk with the same content
+    #[test]
+    fn non_cubic_matches_cubic() {
+        let cube = chunk_buffer::<16, 16, 16>(transparent_sphere::<16>);
+        let tall = chunk_buffer::<16, 32, 16>(|x, y, z| {
+            if y < 16 {
+                transparent_sphere::<16>(x, y, z)
+            } else {
+                0
+            }
+        });
+        let transparent_blocks = BTreeSet::from([2]);
+        let mut mesher1 = bgm::Mesher::<16>::new();
+        mesher1.mesh(&cube, &transparent_blocks);
+        let mut mesher2 = bgm::ChunkMesher::<16, 32, 16>::new();
+        mesher2.mesh(&tall, &transparent_blocks);
+        for (quads1, quads2) in mesher1.quads.iter().zip(mesher2.quads.iter()) {
+            let quads1: Vec<_> = quads1.iter().map(|q| q.debug_quad()).collect();
+            let quads2: Vec<_> = quads2.iter().map(|q| q.debug_quad()).collect();
+            assert_eq!(quads1, quads2);
+        }
+    }
+
+    /// Ensures that u8 and u32 voxel buffers mesh like u16 ones, and that u32 ids are kept whole
+    #[test]
+    fn voxel_types() {
+        let voxels = test_buffer::<CS>();
+        let voxels_u8: Vec<u8> = voxels.iter().map(|&v| v as u8).collect();
+        let voxels_u32: Vec<u32> = voxels.iter().map(|&v| v as u32).collect();
+        let mut mesher1 = bgm::Mesher::<CS>::new();
+        mesher1.mesh(&voxels, &BTreeSet::from([2]));
+        let mut mesher2 = bgm::Mesher::<CS>::new();
+        mesher2.mesh(&voxels_u8, &BTreeSet::from([2]));
+        let mut mesher3 = bgm::Mesher::<CS>::new();
+        mesher3.mesh(&voxels_u32, &BTreeSet::from([2]));
+        assert_eq!(mesher1.quads, mesher2.quads);
+        assert_eq!(mesher1.quads, mesher3.quads);
+
+        let mut voxels = vec![0u32; bgm::Mesher::<CS>::CS_P3];
+        voxels[bgm::pad_linearize::<CS>(0, 0, 0)] = 100_000;
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+        assert_eq!(mesher.quads[bgm::Face::Up as usize][0].voxel_id(), 100_000);
+
+        // AO takes 8 bits of the id
+        voxels[bgm::pad_linearize::<CS>(0, 0, 0)] = (1 << 24) - 1;
+        mesher.clear();
+        mesher.mesh_ao(&voxels, &BTreeSet::new());
+        assert_eq!(
+            mesher.quads[bgm::Face::Up as usize][0].voxel_id(),
+            (1 << 24) - 1
+        );
+
+        // AO and light take 16 bits of the id
+        voxels[bgm::pad_linearize::<CS>(0, 0, 0)] = u16::MAX as u32;
+        let light = vec![15; voxels.len()];
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            ..Default::default()
+        };
+        mesher.clear();
+        mesher.mesh_with(&voxels, &BTreeSet::new(), &attributes, &bgm::MergeSameId);
+        let quad = mesher.quads[bgm::Face::Up as usize][0];
+        assert_eq!((quad.voxel_id(), quad.light()), (u16::MAX as u64, Some(15)));
+
+        // The biggest ids of bigger chunks
+        let mut voxels = vec![0u32; bgm::Mesher::<126>::CS_P3];
+        voxels[bgm::pad_linearize::<126>(0, 0, 0)] = (1 << 27) - 1;
+        let mut mesher = bgm::Mesher::<126>::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+        assert_eq!(bgm::Quad::<126>::ID_BITS, 27);
+        assert_eq!(
+            mesher.quads[bgm::Face::Up as usize][0].voxel_id(),
+            (1 << 27) - 1
+        );
+        let mut voxels = vec![0u32; bgm::ChunkMesher::<32, 256, 32>::VOXELS_P];
+        voxels[bgm::ChunkMesher::<32, 256, 32>::pad_linearize(0, 255, 0)] = (1 << 25) - 1;
+        let mut mesher = bgm::ChunkMesher::<32, 256, 32>::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+        assert_eq!(bgm::Quad::<32, 256, 32>::ID_BITS, 25);
+        assert_eq!(
+            mesher.quads[bgm::Face::Up as usize][0].voxel_id(),
+            (1 << 25) - 1
+        );
+        // With AO and light, 9 bits are left
+        let mut voxels = vec![0u16; bgm::ChunkMesher::<32, 256, 32>::VOXELS_P];
+        voxels[bgm::ChunkMesher::<32, 256, 32>::pad_linearize(0, 255, 0)] = (1 << 9) - 1;
+        let light = vec![15; voxels.len()];
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            ..Default::default()
+        };
+        mesher.clear();
+        mesher.mesh_with(&voxels, &BTreeSet::new(), &attributes, &bgm::MergeSameId);
+        let quad = mesher.quads[bgm::Face::Up as usize][0];
+        assert_eq!((quad.voxel_id(), quad.light()), ((1 << 9) - 1, Some(15)));
+    }
+
+    /// Ensures that tall chunks keep room for realistic ids with AO and light, on quads spanning the whole chunk
+    #[test]
+    fn tall_chunk_ao_light() {
+        type Tall = bgm::ChunkMesher<32, 256, 32>;
+        let voxels = chunk_buffer::<32, 256, 32>(|x, _y, z| if x == 0 && z == 0 { 300 } else { 0 });
+        let light = vec![15; voxels.len()];
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            ..Default::default()
+        };
+        let mut mesher = Tall::new();
+        mesher.mesh_with(&voxels, &BTreeSet::new(), &attributes, &bgm::MergeSameId);
+        for quads in mesher.quads.iter() {
+            assert_eq!(quads.len(), 1);
+            assert_eq!((quads[0].voxel_id(), quads[0].light()), (300, Some(15)));
+        }
+        let right = mesher.quads[bgm::Face::Right as usize][0];
+        assert_eq!(right.xyz(), [1, 256, 0]);
+        assert_eq!((right.width(), right.height()), (256, 1));
+        let front = mesher.quads[bgm::Face::Front as usize][0];
+        assert_eq!((front.width(), front.height()), (1, 256));
+        assert_eq!(front.ao(), [0; 4]);
+    }
+
+    /// Ensures that ids too big for the quads are rejected instead of truncated
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn voxel_id_overflow() {
+        let mut voxels = vec![0u32; bgm::Mesher::<126>::CS_P3];
+        voxels[bgm::pad_linearize::<126>(0, 0, 0)] = 1 << 28;
+        let mut mesher = bgm::Mesher::<126>::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+    }
+
+    /// Ensures that ids too big for quads with AO are rejected instead of truncated
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn voxel_id_overflow_ao() {
+        let mut voxels = vec![0u32; bgm::Mesher::<CS>::CS_P3];
+        voxels[bgm::pad_linearize::<CS>(0, 0, 0)] = 1 << 24;
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh_ao(&voxels, &BTreeSet::new());
+    }
+
+    /// Ensures that ids too big for quads with AO and light are rejected instead of truncated
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn voxel_id_overflow_light() {
+        let mut voxels = vec![0u32; bgm::Mesher::<CS>::CS_P3];
+        voxels[bgm::pad_linearize::<CS>(0, 0, 0)] = 100_000;
+        let light = vec![15; voxels.len()];
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            ..Default::default()
+        };
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh_with(&voxels, &BTreeSet::new(), &attributes, &bgm::MergeSameId);
+    }
+
+    /// Ensures that tall chunks with AO and light, which leave 9 bits to the id, reject bigger ids
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn voxel_id_overflow_light_tall_chunk() {
+        let mut voxels = vec![0u16; bgm::ChunkMesher::<32, 256, 32>::VOXELS_P];
+        voxels[bgm::ChunkMesher::<32, 256, 32>::pad_linearize(0, 0, 0)] = 1 << 9;
+        let light = vec![15; voxels.len()];
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            ..Default::default()
+        };
+        let mut mesher = bgm::ChunkMesher::<32, 256, 32>::new();
+        mesher.mesh_with(&voxels, &BTreeSet::new(), &attributes, &bgm::MergeSameId);
+    }
+
+    /// Ensures that ids too big for the quads of tall chunks are rejected instead of truncated
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn voxel_id_overflow_tall_chunk() {
+        let mut voxels = vec![0u32; bgm::ChunkMesher::<32, 256, 32>::VOXELS_P];
+        voxels[bgm::ChunkMesher::<32, 256, 32>::pad_linearize(0, 0, 0)] = 1 << 25;
+        let mut mesher = bgm::ChunkMesher::<32, 256, 32>::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+    }
+
+    /// Ensures that the TransparentSet and BTreeSet classifiers give the same results
+    #[test]
+    fn same_results_across_classifiers() {
+        let voxels = test_buffer::<CS>();
+        let transparent_set = bgm::TransparentSet::from([2]);
+        let transparent_tree = BTreeSet::from([2]);
+        let mut mesher1 = bgm::Mesher::<CS>::new();
+        mesher1.mesh(&voxels, &transparent_tree);
+        let mut mesher2 = bgm::Mesher::<CS>::new();
+        mesher2.mesh(&voxels, &transparent_set);
+        assert_eq!(mesher1.quads, mesher2.quads);
+        assert_eq!(
+            bgm::compute_opaque_mask::<CS, _>(&voxels, &transparent_tree),
+            bgm::compute_opaque_mask::<CS, _>(&voxels, &transparent_set)
+        );
+        assert_eq!(
+            bgm::compute_transparent_mask::<CS, _>(&voxels, &transparent_tree),
+            bgm::compute_transparent_mask::<CS, _>(&voxels, &transparent_set)
+        );
+        // ids that don't fit in the set are opaque
+        assert_eq!(
+            bgm::VoxelClassifier::classify(&transparent_set, 2 + (1u32 << 16)),
+            bgm::VoxelKind::Opaque
+        );
+    }
+
+    /// Ensures that mesh_ao and fast_mesh_ao return the same results
+    #[test]
+    fn same_results_ao() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&voxels, &transparent_blocks);
+        let trans_mask = bgm::compute_transparent_mask::<CS, _>(&voxels, &transparent_blocks);
+        let mut mesher1 = bgm::Mesher::<CS>::new();
+        mesher1.mesh_ao(&voxels, &transparent_blocks);
+        let mut mesher2 = bgm::Mesher::<CS>::new();
+        mesher2.fast_mesh_ao(&voxels, &opaque_mask, &trans_mask);
+        assert_eq!(mesher1.quads, mesher2.quads);
+        assert!(mesher1.quads.iter().flatten().all(|quad| quad.has_ao()));
+    }
+
+    /// Checks the AO of a row of voxels with a voxel on top of its end,
+    /// the faces next to that voxel are occluded and don't merge with the others
+    #[test]
+    fn ao_values() {
+        let mut voxels = vec![0u16; bgm::Mesher::<CS>::CS_P3];
+        for x in 0..4 {
+            voxels[bgm::pad_linearize::<CS>(x, 0, 0)] = 1;
+        }
+        voxels[bgm::pad_linearize::<CS>(3, 1, 0)] = 1;
+        let transparents = bgm::TransparentSet::new();
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh(&voxels, &transparents);
+        assert_eq!(mesher.quads[bgm::Face::Up as usize].len(), 2);
+        mesher.clear();
+        mesher.mesh_ao(&voxels, &transparents);
+        let up = &mesher.quads[bgm::Face::Up as usize];
+        assert_eq!(up.len(), 3);
+        // the Up face of x=2 is occluded on its +x side
+        let occluded = up.iter().find(|quad| quad.xyz() == [2, 1, 0]).unwrap();
+        assert_eq!(occluded.ao(), [1, 1, 0, 0]);
+        assert_eq!(occluded.voxel_id(), 1);
+        let vertices = bgm::Face::Up.vertices_packed(*occluded);
+        assert_eq!(vertices.map(|v| v.ao()), [1, 1, 0, 0]);
+        assert_eq!(vertices[0].xyz(), [3, 1, 1]);
+        // the Up faces of x=0 and x=1 are merged and unoccluded
+        let merged = up.iter().find(|quad| quad.xyz() == [0, 1, 0]).unwrap();
+        assert_eq!((merged.width(), merged.ao()), (2, [0; 4]));
+    }
+
+    /// Checks the AO of every vertex against the voxels around it, for all faces
+    #[test]
+    fn ao_matches_neighbours() {
+        let voxels = test_buffer::<CS>();
+        let transparents = bgm::TransparentSet::from([2]);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh_ao(&voxels, &transparents);
+        let cs_p = CS as i32 + 2;
+        let mut occluded = 0;
+        let opaque = |p: [i32; 3]| {
+            voxels[((p[2] + 1) + (p[0] + 1) * cs_p + (p[1] + 1) * cs_p * cs_p) as usize] == 1
+        };
+        for (face, quads) in mesher.quads.iter().enumerate() {
+            let face = bgm::Face::from(face as u8);
+            let n = face.n();
+            let axis = n.iter().position(|&d| d != 0).unwrap();
+            let (t, u) = ((axis + 1) % 3, (axis + 2) % 3);
+            for &quad in quads {
+                let vertices = face.vertices_packed(quad);
+                let positions = vertices.map(|v| v.xyz().map(|c| c as i32));
+                let sum: [i32; 3] = core::array::from_fn(|i| positions.iter().map(|p| p[i]).sum());
+                for (vertex, p) in vertices.iter().zip(positions) {
+                    // the air cell of the face touching the vertex, and its neighbours around the vertex
+                    let mut own = p;
+                    own[axis] = if n[axis] > 0 { p[axis] } else { p[axis] - 1 };
+                    for a in [t, u] {
+                        own[a] = if 4 * p[a] < sum[a] { p[a] } else { p[a] - 1 };
+                    }
+                    let other = |p_a: i32, own_a: i32| if own_a == p_a { p_a - 1 } else { p_a };
+                    let mut side1 = own;
+                    side1[t] = other(p[t], own[t]);
+                    let mut side2 = own;
+                    side2[u] = other(p[u], own[u]);
+                    let mut corner = side1;
+                    corner[u] = side2[u];
+                    let (side1, side2, corner) = (opaque(side1), opaque(side2), opaque(corner));
+                    let expected = if side1 && side2 {
+                        3
+                    } else {
+                        side1 as u32 + side2 as u32 + corner as u32
+                    };
+                    assert_eq!(vertex.ao(), expected, "{face:?} {}", quad.debug_quad());
+                    occluded += (expected > 0) as usize;
+                }
+            }
+        }
+        assert!(occluded > 0);
+    }
+
+    /// Ensures that mesh_with and fast_mesh_with return the same results with AO and light
+    #[test]
+    fn same_results_attributes() {
+        let voxels = test_buffer::<CS>();
+        let light: Vec<u8> = (0..voxels.len()).map(|i| (i / 7 % 16) as u8).collect();
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            smooth_light: Some(&light),
+        };
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&voxels, &transparent_blocks);
+        let trans_mask = bgm::compute_transparent_mask::<CS, _>(&voxels, &transparent_blocks);
+        let mut mesher1 = bgm::Mesher::<CS>::new();
+        mesher1.mesh_with(&voxels, &transparent_blocks, &attributes, &bgm::MergeSameId);
+        let mut mesher2 = bgm::Mesher::<CS>::new();
+        mesher2.fast_mesh_with(
+            &voxels,
+            &opaque_mask,
+            &trans_mask,
+            &attributes,
+            &bgm::MergeSameId,
+        );
+        assert_eq!(mesher1.quads, mesher2.quads);
+        assert!(
+            mesher1
+                .quads
+                .iter()
+                .flatten()
+                .all(|quad| quad.voxel_id() <= 2)
+        );
+    }
+
+    /// Checks the light of a row of voxels with a darker cell above one of them,
+    /// which splits the Up quads
+    #[test]
+    fn light_values() {
+        let mut voxels = vec![0u16; bgm::Mesher::<CS>::CS_P3];
+        let mut light = vec![15u8; bgm::Mesher::<CS>::CS_P3];
+        for x in 0..4 {
+            voxels[bgm::pad_linearize::<CS>(x, 0, 0)] = 3;
+        }
+        light[bgm::pad_linearize::<CS>(2, 1, 0)] = 10;
+        let attributes = bgm::FaceAttributes {
+            light: Some(&light),
+            ..Default::default()
+        };
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh_with(
+            &voxels,
+            &bgm::TransparentSet::new(),
+            &attributes,
+            &bgm::MergeSameId,
+        );
+        let up: Vec<_> = mesher.quads[bgm::Face::Up as usize]
+            .iter()
+            .map(|quad| (quad.xyz()[0], quad.width(), quad.light(), quad.voxel_id()))
+            .collect();
+        assert_eq!(
+            up,
+            [
+                (0, 2, Some(15), 3),
+                (2, 1, Some(10), 3),
+                (3, 1, Some(15), 3)
+            ]
+        );
+        assert_eq!(mesher.quads[bgm::Face::Down as usize].len(), 1);
+        assert!(!mesher.quads[bgm::Face::Down as usize][0].has_ao());
+    }
+
+    /// Checks the smooth light of a row of voxels with a darker cell diagonal to one of them,
+    /// which splits the Up quads wherever a vertex touches it
+    #[test]
+    fn smooth_light_values() {
+        let mut voxels = vec![0u16; bgm::Mesher::<CS>::CS_P3];
+        let mut light = vec![15u8; bgm::Mesher::<CS>::CS_P3];
+        for x in 0..4 {
+            voxels[bgm::pad_linearize::<CS>(x, 0, 1)] = 1;
+        }
+        light[bgm::pad_linearize::<CS>(3, 1, 0)] = 3;
+        let attributes = bgm::FaceAttributes {
+            smooth_light: Some(&light),
+            ..Default::default()
+        };
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh_with(
+            &voxels,
+            &bgm::TransparentSet::new(),
+            &attributes,
+            &bgm::MergeSameId,
+        );
+        let up = &mesher.quads[bgm::Face::Up as usize];
+        let lights: Vec<_> = up
+            .iter()
+            .map(|&quad| {
+                let (_, light) = bgm::Face::Up.vertices_smooth_light(quad, &light);
+                (quad.xyz()[0], quad.width(), light)
+            })
+            .collect();
+        // Up vertices are ordered +x+z, +x-z, -x+z, -x-z
+        assert_eq!(
+            lights,
+            [
+                (0, 2, [15; 4]),
+                (2, 1, [15, 12, 15, 15]),
+                (3, 1, [15, 12, 15, 12])
+            ]
+        );
+        // without smooth light the row is a single quad
+        mesher.clear();
+        mesher.mesh(&voxels, &bgm::TransparentSet::new());
+        assert_eq!(mesher.quads[bgm::Face::Up as usize].len(), 1);
+    }
+
+    /// Ensures that NeverMerge outputs 1 quad per visible face
+    #[test]
+    fn never_merge() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let mut mesher1 = bgm::Mesher::<CS>::new();
+        mesher1.mesh(&voxels, &transparent_blocks);
+        let mut mesher2 = bgm::Mesher::<CS>::new();
+        mesher2.mesh_with(
+            &voxels,
+            &transparent_blocks,
+            &bgm::FaceAttributes::default(),
+            &bgm::NeverMerge,
+        );
+        for (merged, unmerged) in mesher1.quads.iter().zip(mesher2.quads.iter()) {
+            let area: u64 = merged.iter().map(|q| q.width() * q.height()).sum();
+            assert_eq!(area, unmerged.len() as u64);
+            assert!(unmerged.iter().all(|q| q.width() == 1 && q.height() == 1));
+        }
+    }
+
+    /// Checks merging with custom merge keys on a row of alternating voxels
+    #[test]
+    fn merge_keys() {
+        let mut voxels = vec![0u16; bgm::Mesher::<CS>::CS_P3];
+        for x in 0..4 {
+            voxels[bgm::pad_linearize::<CS>(x, 0, 0)] = 1 + (x % 2) as u16;
+        }
+        let transparents = bgm::TransparentSet::new();
+        let attributes = bgm::FaceAttributes::default();
+        let up_widths = |mesher: &bgm::Mesher<CS>| -> Vec<u64> {
+            mesher.quads[bgm::Face::Up as usize]
+                .iter()
+                .map(|q| q.width())
+                .collect()
+        };
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh(&voxels, &transparents);
+        assert_eq!(up_widths(&mesher), [1, 1, 1, 1]);
+        // 1 and 2 share a texture
+        mesher.clear();
+        mesher.mesh_with(&voxels, &transparents, &attributes, &|_: u16| Some(0));
+        assert_eq!(up_widths(&mesher), [4]);
+        // 2 is animated, 1 and 2 share a texture otherwise
+        mesher.clear();
+        mesher.mesh_with(&voxels, &transparents, &attributes, &|v: u16| {
+            (v != 2).then_some(0)
+        });
+        assert_eq!(up_widths(&mesher), [1, 1, 1, 1]);
+        voxels[bgm::pad_linearize::<CS>(1, 0, 0)] = 3;
+        mesher.clear();
+        mesher.mesh_with(&voxels, &transparents, &attributes, &|v: u16| {
+            (v != 2).then_some(0)
+        });
+        assert_eq!(up_widths(&mesher), [3, 1]);
+    }
+
+    /// Ensures that ChunkMasks keeps its masks in sync with its voxels through edits
+    #[test]
+    fn chunk_masks_edits() {
+        let transparents = bgm::TransparentSet::from([2]);
+        let mut chunk =
+            bgm::ChunkMasks::<u16, _, CS>::from_voxels(test_buffer::<CS>(), &transparents);
+        chunk.fill_region([10, 20, 5], [40, 22, 61], 2);
+        chunk.fill_region([0, 0, 0], [CS, 1, CS], 1);
+        chunk.fill_region([30, 0, 30], [31, CS, 32], 0);
+        chunk.set_many((0..CS).map(|i| ([i, i, i], (i % 3) as u16)));
+        chunk.set(3, 4, 5, 2);
+        chunk.set_padded(0, 1, 1, 1);
+        chunk.set_padded(CS + 1, CS + 1, CS + 1, 2);
+        assert_eq!(chunk.get(3, 4, 5), 2);
+        assert_eq!(chunk.get_padded(0, 1, 1), 1);
+        assert_eq!(chunk.get(30, 0, 31), 0);
+        assert_eq!(chunk.get(12, 21, 60), 2);
+        assert_eq!(
+            chunk.opaque_mask(),
+            &*bgm::compute_opaque_mask::<CS, _>(chunk.voxels(), &transparents)
+        );
+        assert_eq!(
+            chunk.trans_mask(),
+            &*bgm::compute_transparent_mask::<CS, _>(chunk.voxels(), &transparents)
+        );
+        let mut mesher1 = bgm::Mesher::<CS>::new();
+        mesher1.mesh(chunk.voxels(), &transparents);
+        let mut mesher2 = bgm::Mesher::<CS>::new();
+        mesher2.fast_mesh(chunk.voxels(), chunk.opaque_mask(), chunk.trans_mask());
+        assert_eq!(mesher1.quads, mesher2.quads);
+    }
+
+    /// Ensures that ChunkMasks rejects positions out of the chunk instead of writing its padding
+    #[test]
+    #[should_panic(expected = "position out of the chunk")]
+    fn chunk_masks_set_out_of_chunk() {
+        let transparents = bgm::TransparentSet::from([2]);
+        let mut chunk = bgm::ChunkMasks::<u16, _, 8, 12, 6>::new(&transparents);
+        chunk.set(3, 4, 6, 1);
+    }
+
+    /// Ensures that ChunkMasks rejects regions out of the chunk
+    #[test]
+    #[should_panic(expected = "region out of the chunk")]
+    fn chunk_masks_fill_out_of_chunk() {
+        let transparents = bgm::TransparentSet::from([2]);
+        let mut chunk = bgm::ChunkMasks::<u16, _, 8, 12, 6>::new(&transparents);
+        chunk.fill_region([0, 0, 0], [9, 1, 1], 1);
+    }
+
+    /// Builds a chunk's padding from a world function sampled through the neighbour accessor
+    #[test]
+    fn chunk_from_neighbours() {
+        const DIMS: [usize; 3] = [8, 12, 6];
+        type Chunk<'a> = bgm::ChunkMasks<u16, &'a bgm::TransparentSet, 8, 12, 6>;
+        let world = |[x, y, z]: [i32; 3]| ((x * 7 + y * 3 + z * 5).rem_euclid(4)) as u16;
+        let transparents = bgm::TransparentSet::from([2]);
+        let mut center = vec![0u16; 8 * 12 * 6];
+        for y in 0..12 {
+            for x in 0..8 {
+                for z in 0..6 {
+                    center[z + x * 6 + y * 6 * 8] = world([x, y, z].map(|c| c as i32));
+                }
+            }
+        }
+        let chunk = Chunk::from_neighbours(
+            &center,
+            |offset, pos| {
+                Some(world(core::array::from_fn(|i| {
+                    offset[i] * DIMS[i] as i32 + pos[i] as i32
+                })))
+            },
+            bgm::MissingNeighbour::Air,
+            &transparents,
+        );
+        for y in 0..14 {
+            for x in 0..10 {
+                for z in 0..8 {
+                    let expected = world([x, y, z].map(|c| c as i32 - 1));
+                    assert_eq!(chunk.get_padded(x, y, z), expected);
+                }
+            }
+        }
+        assert_eq!(
+            chunk.opaque_mask(),
+            &*bgm::ChunkMesher::<8, 12, 6>::compute_opaque_mask(chunk.voxels(), &transparents)
+        );
+
+        // A full chunk with missing neighbours is only meshed on the sides treated as air
+        let full = vec![1u16; 8 * 12 * 6];
+        let mut mesher = bgm::ChunkMesher::<8, 12, 6>::new();
+        for (missing, quads) in [
+            (bgm::MissingNeighbour::Air, 6),
+            (bgm::MissingNeighbour::Solid(1), 0),
+        ] {
+            let chunk = Chunk::from_neighbours(&full, |_, _| None, missing, &transparents);
+            mesher.clear();
+            mesher.fast_mesh(chunk.voxels(), chunk.opaque_mask(), chunk.trans_mask());
+            assert_eq!(mesher.quads.iter().map(|q| q.len()).sum::<usize>(), quads);
+        }
+    }
+
+    /// Ensures that meshing an unpadded chunk with its neighbours' border layers matches meshing the padded chunk
+    #[test]
+    fn same_results_unpadded() {
+        const DIMS: [usize; 3] = [8, 12, 6];
+        let world = |[x, y, z]: [i32; 3]| match (x * 7 + y * 3 + z).rem_euclid(5) {
+            0 | 1 => 0u16,
+            v => v as u16,
+        };
+        let at = |x: usize, y: usize, z: usize| world([x, y, z].map(|c| c as i32));
+        let transparents = bgm::TransparentSet::from([2]);
+        let mut center = Vec::new();
+        for y in 0..12 {
+            for x in 0..8 {
+                for z in 0..6 {
+                    center.push(at(x, y, z));
+                }
+            }
+        }
+        let padded = bgm::ChunkMasks::<u16, _, 8, 12, 6>::from_neighbours(
+            &center,
+            |offset, pos| {
+                Some(world(core::array::from_fn(|i| {
+                    offset[i] * DIMS[i] as i32 + pos[i] as i32
+                })))
+            },
+            bgm::MissingNeighbour::Air,
+            &transparents,
+        );
+        let layer = |f: &dyn Fn(usize, usize) -> [i32; 3], n1: usize, n2: usize| -> Vec<u16> {
+            let mut layer = Vec::new();
+            for b in 0..n2 {
+                for a in 0..n1 {
+                    layer.push(world(f(a, b)));
+                }
+            }
+            layer
+        };
+        let up = layer(&|z, x| [x as i32, 12, z as i32], 6, 8);
+        let down = layer(&|z, x| [x as i32, -1, z as i32], 6, 8);
+        let right = layer(&|z, y| [8, y as i32, z as i32], 6, 12);
+        let left = layer(&|z, y| [-1, y as i32, z as i32], 6, 12);
+        let front = layer(&|x, y| [x as i32, y as i32, 6], 8, 12);
+        let back = layer(&|x, y| [x as i32, y as i32, -1], 8, 12);
+        let mut mesher1 = bgm::ChunkMesher::<8, 12, 6>::new();
+        mesher1.mesh(padded.voxels(), &transparents);
+        let mut mesher2 = bgm::ChunkMesher::<8, 12, 6>::new();
+        mesher2.mesh_unpadded(
+            &center,
+            [&up, &down, &right, &left, &front, &back],
+            &transparents,
+        );
+        assert!(mesher1.quads.iter().all(|quads| !quads.is_empty()));
+        assert_eq!(mesher1.quads, mesher2.quads);
+    }
+
+    /// Ensures that remeshing the edited part of a chunk gives the same quads as meshing it again
+    #[test]
+    fn remesh_edits() {
+        const CS: usize = 30;
+        let mut chunk = bgm::ChunkMasks::<u16, _, CS>::from_voxels(
+            test_buffer::<CS>(),
+            bgm::TransparentSet::from([2]),
+        );
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            ..Default::default()
+        };
+        let sorted = |quads: &[Vec<bgm::Quad<CS>>; 6]| {
+            quads.clone().map(|mut quads| {
+                quads.sort();
+                quads
+            })
+        };
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.fast_mesh_with(
+            chunk.voxels(),
+            chunk.opaque_mask(),
+            chunk.trans_mask(),
+            &attributes,
+            &bgm::MergeSameId,
+        );
+        let edits = [
+            ([15, 15, 15], [16, 16, 16], 0),
+            ([0, 0, 0], [1, 1, 1], 1),
+            ([8, 10, 3], [9, 20, 27], 3),
+            ([CS - 1, 4, 0], [CS, 5, CS], 2),
+            ([2, 12, 12], [28, 18, 18], 0),
+        ];
+        for (min, max, voxel) in edits {
+            chunk.fill_region(min, max, voxel);
+            mesher.fast_remesh_with(
+                chunk.voxels(),
+                chunk.opaque_mask(),
+                chunk.trans_mask(),
+                min,
+                max,
+                &attributes,
+                &bgm::MergeSameId,
+            );
+            let mut expected = bgm::Mesher::<CS>::new();
+            expected.fast_mesh_with(
+                chunk.voxels(),
+                chunk.opaque_mask(),
+                chunk.trans_mask(),
+                &attributes,
+                &bgm::MergeSameId,
+            );
+            assert_eq!(sorted(&mesher.quads), sorted(&expected.quads));
+            for face in 0..6 {
+                let face = bgm::Face::from(face);
+                assert_eq!(mesher.face_bounds(face), expected.face_bounds(face));
+            }
+        }
+    }
+
+    /// Ensures that every layout gives the same masks, quads and smooth light
+    #[test]
+    fn same_results_across_layouts() {
+        let zxy = layout_results::<bgm::Zxy>();
+        assert!(zxy.1.iter().flatten().any(|quads| !quads.is_empty()));
+        assert_eq!(zxy, layout_results::<bgm::Xzy>());
+        assert_eq!(zxy, layout_results::<bgm::Yzx>());
+    }
+
+    /// Ensures that transparent quads are split from the opaque ones without changing the quads
+    #[test]
+    fn split_transparent() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&voxels, &transparent_blocks);
+        let trans_mask = bgm::compute_transparent_mask::<CS, _>(&voxels, &transparent_blocks);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh(&voxels, &transparent_blocks);
+        let expected = mesher.quads.clone();
+        assert!(
+            mesher
+                .transparent_quads
+                .iter()
+                .all(|quads| quads.is_empty())
+        );
+
+        let mut split = bgm::Mesher::<CS>::new();
+        split.split_transparent = true;
+        split.mesh(&voxels, &transparent_blocks);
+        let mut fast_split = bgm::Mesher::<CS>::new();
+        fast_split.split_transparent = true;
+        fast_split.fast_mesh(&voxels, &opaque_mask, &trans_mask);
+        assert_eq!(split.quads, fast_split.quads);
+        assert_eq!(split.transparent_quads, fast_split.transparent_quads);
+        for face in 0..6 {
+            assert!(split.quads[face].iter().all(|quad| quad.voxel_id() == 1));
+            assert!(!split.transparent_quads[face].is_empty());
+            assert!(
+                split.transparent_quads[face]
+                    .iter()
+                    .all(|quad| quad.voxel_id() == 2)
+            );
+            let mut quads = [&split.quads[face][..], &split.transparent_quads[face]].concat();
+            let mut expected = expected[face].clone();
+            quads.sort();
+            expected.sort();
+            assert_eq!(quads, expected);
+        }
+
+        // Opaque and transparent faces with the same merge key aren't merged together
+        split.clear();
+        split.mesh_with(
+            &voxels,
+            &transparent_blocks,
+            &bgm::FaceAttributes::default(),
+            &|_| Some(0),
+        );
+        assert!(
+            split
+                .quads
+                .iter()
+                .flatten()
+                .all(|quad| quad.voxel_id() == 1)
+        );
+        assert!(
+            split
+                .transparent_quads
+                .iter()
+                .flatten()
+                .all(|quad| quad.voxel_id() == 2)
+        );
+    }
+
+    /// Ensures that grouped quads are contiguous per key and face and keep their meshing order
+    #[test]
+    fn quad_groups() {
+        let voxels =
+            chunk_buffer::<CS, CS, CS>(|x, y, z| match transparent_sphere::<CS>(x, y, z) {
+                1 => 1 + (y % 3) as u16,
+                v => v,
+            });
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh(&voxels, &bgm::TransparentSet::from([2]));
+        // Materials 1 and 3 share a key
+        let material = |quad: bgm::Quad<CS>| quad.voxel_id().min(2);
+        let groups = bgm::QuadGroups::new(&mesher.quads, material);
+        assert_eq!(
+            groups.quads.len(),
+            mesher.quads.iter().map(|quads| quads.len()).sum::<usize>()
+        );
+        assert_eq!(
+            groups
+                .groups
+                .iter()
+                .map(|group| group.key)
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+        let mut end = 0;
+        for group in groups.groups.iter() {
+            assert_eq!(group.range.start, end);
+            assert_eq!(group.faces[0].start, group.range.start);
+            assert_eq!(group.faces[5].end, group.range.end);
+            end = group.range.end;
+            for (face, range) in group.faces.iter().enumerate() {
+                let expected: Vec<_> = mesher.quads[face]
+                    .iter()
+                    .copied()
+                    .filter(|&quad| material(quad) == group.key)
+                    .collect();
+                assert!(!expected.is_empty());
+                assert_eq!(&groups.quads[range.clone()], &expected[..]);
+            }
+            assert_eq!(
+                groups.get(&group.key).unwrap(),
+                &groups.quads[group.range.clone()]
+            );
+        }
+        assert_eq!(end, groups.quads.len());
+        assert_eq!(groups.iter().count(), 12);
+        assert!(groups.get(&3).is_none());
+    }
+
+    /// Ensures that transparent quads are sorted back to front, before and after the camera moves
+    #[test]
+    fn back_to_front() {
+        let voxels = test_buffer::<CS>();
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.split_transparent = true;
+        mesher.mesh(&voxels, &bgm::TransparentSet::from([2]));
+        // The centers of the quads from their vertices
+        let centers: Vec<[f32; 3]> = mesher
+            .transparent_quads
+            .iter()
+            .enumerate()
+            .flat_map(|(face, quads)| {
+                let face = bgm::Face::from(face as u8);
+                quads.iter().map(move |&quad| {
+                    let vertices = face.vertices_packed(quad);
+                    core::array::from_fn(|i| {
+                        vertices.iter().map(|v| v.xyz()[i] as f32).sum::<f32>() / 4.
+                    })
+                })
+            })
+            .collect();
+        let mut sorter = bgm::QuadSorter::new(&mesher.transparent_quads);
+        let all_indices = bgm::indices(centers.len());
+        for camera in [
+            [-5., 30., 31.],
+            [8.5, 20., 31.],
+            [9., 20., 31.],
+            [70., 0., 2.],
+        ] {
+            let distance = |i: u32| -> f32 {
+                (0..3)
+                    .map(|axis| (centers[i as usize][axis] - camera[axis]).powi(2))
+                    .sum()
+            };
+            let indices = sorter.sort(camera).to_vec();
+            let order = sorter.order();
+            assert!(order.windows(2).all(|w| distance(w[0]) >= distance(w[1])));
+            for (quad_indices, &i) in indices.chunks(6).zip(order) {
+                assert_eq!(
+                    quad_indices,
+                    &all_indices[(i as usize * 6)..(i as usize * 6 + 6)]
+                );
+            }
+            let mut fresh = bgm::QuadSorter::new(&mesher.transparent_quads);
+            fresh.sort(camera);
+            assert!(
+                fresh
+                    .order()
+                    .iter()
+                    .zip(order)
+                    .all(|(&a, &b)| distance(a) == distance(b))
+            );
+        }
+    }
+
+    /// Ensures that the face bounds computed while meshing contain exactly the quads' vertices,
+    /// and that faces looking away from the camera are culled
+    #[test]
+    fn face_bounds() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.split_transparent = true;
+        mesher.mesh(&voxels, &transparent_blocks);
+        let vertex_bounds = |face: bgm::Face, quads: &[bgm::Quad<CS>]| {
+            quads.iter().fold(bgm::Aabb::EMPTY, |bounds, &quad| {
+                face.vertices_packed(quad)
+                    .iter()
+                    .fold(bounds, |bounds, vertex| {
+                        let p = vertex.xyz().map(|c| c as f32);
+                        bounds.union(bgm::Aabb { min: p, max: p })
+                    })
+            })
+        };
+        for face in 0..6 {
+            let face = bgm::Face::from(face);
+            assert_eq!(
+                mesher.face_bounds(face),
+                vertex_bounds(face, &mesher.quads[face as usize])
+            );
+            assert_eq!(
+                mesher.transparent_face_bounds(face),
+                vertex_bounds(face, &mesher.transparent_quads[face as usize])
+            );
+        }
+
+        // The sphere has a radius of 4 around 31;31;31
+        let chunk = bgm::Aabb {
+            min: [0.; 3],
+            max: [CS as f32; 3],
+        };
+        assert_eq!(chunk.visible_faces([31.; 3]), [true; 6]);
+        assert_eq!(
+            chunk.visible_faces([31., 100., 31.]),
+            [true, false, true, true, true, true]
+        );
+        assert_eq!(
+            chunk
+                .to_world([0., -200., 0.], 2.)
+                .visible_faces([31., 100., 31.]),
+            [true, false, true, true, true, true]
+        );
+        // From above and right of the sphere's center, its Down and Left faces can be culled
+        let camera = [40., 50., 31.];
+        assert!(!bgm::Face::Down.is_visible(&mesher.face_bounds(bgm::Face::Down), camera));
+        assert!(!bgm::Face::Left.is_visible(&mesher.face_bounds(bgm::Face::Left), camera));
+        assert!(bgm::Face::Right.is_visible(&mesher.face_bounds(bgm::Face::Right), camera));
+        assert!(!bgm::Face::Up.is_visible(&bgm::Aabb::EMPTY, camera));
+
+        mesher.clear();
+        assert!(mesher.face_bounds(bgm::Face::Up).is_empty());
+    }
+
+    /// Ensures that the bounds of the whole output contain every face group
+    #[test]
+    fn chunk_bounds() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.split_transparent = true;
+        mesher.mesh(&voxels, &transparent_blocks);
+        let face_bounds = (0..6)
+            .map(bgm::Face::from)
+            .fold(bgm::Aabb::EMPTY, |bounds, face| {
+                bounds
+                    .union(mesher.face_bounds(face))
+                    .union(mesher.transparent_face_bounds(face))
+            });
+        assert_eq!(mesher.bounds(), face_bounds);
+        // From the transparent plane at x = 8 to the sphere of radius 4 around 31;31;31
+        assert_eq!(
+            mesher.bounds(),
+            bgm::Aabb {
+                min: [8., 0., 0.],
+                max: [35., CS as f32, CS as f32]
+            }
+        );
+
+        let mut voxels = vec![0u16; bgm::Mesher::<CS>::CS_P3];
+        voxels[bgm::pad_linearize::<CS>(0, 2, 5)] = 1;
+        mesher.clear();
+        mesher.mesh(&voxels, &transparent_blocks);
+        assert_eq!(
+            mesher.bounds(),
+            bgm::Aabb {
+                min: [0., 2., 5.],
+                max: [1., 3., 6.]
+            }
+        );
+
+        mesher.clear();
+        assert!(mesher.bounds().is_empty());
+    }
+
+    /// Ensures that the float buffers match the packed vertices, moved to world space
+    #[test]
+    fn mesh_buffers() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh(&voxels, &transparent_blocks);
+        let (origin, scale) = ([62., -124., 0.5], 2.);
+        let buffers = bgm::MeshBuffers::from_quads(&mesher.quads, origin, scale);
+        let num_quads: usize = mesher.quads.iter().map(Vec::len).sum();
+        assert_eq!(buffers.indices, bgm::indices(num_quads));
+        let mut i = 0;
+        for (face, quads) in mesher.quads.iter().enumerate() {
+            let face = bgm::Face::from(face as u8);
+            for &quad in quads {
+                for vertex in face.vertices_packed(quad) {
+                    let xyz = vertex.xyz();
+                    let position: [f32; 3] =
+                        core::array::from_fn(|i| origin[i] + xyz[i] as f32 * scale);
+                    assert_eq!(buffers.positions[i], position);
+                    assert_eq!(buffers.normals[i], face.n().map(|c| c as f32));
+                    assert_eq!(buffers.uvs[i], [vertex.u() as f32, vertex.v() as f32]);
+                    assert_eq!(buffers.voxel_ids[i] as u64, quad.voxel_id());
+                    i += 1;
+                }
+            }
+        }
+        assert_eq!(i, buffers.positions.len());
+        let interleaved = buffers.interleaved();
+        assert_eq!(interleaved.len(), i);
+        assert_eq!(interleaved[5].position, buffers.positions[5]);
+        assert_eq!(interleaved[5].uv, buffers.uvs[5]);
+
+        // Chunks too tall for packed vertices
+        let mut mesher = bgm::ChunkMesher::<32, 256, 32>::new();
+        let mut voxels = vec![0u16; bgm::ChunkMesher::<32, 256, 32>::VOXELS_P];
+        voxels[bgm::ChunkMesher::<32, 256, 32>::pad_linearize(0, 255, 0)] = 1;
+        mesher.mesh(&voxels, &transparent_blocks);
+        let mut buffers = bgm::MeshBuffers::new();
+        buffers.push_quads(
+            bgm::Face::Up,
+            &mesher.quads[bgm::Face::Up as usize],
+            [0.; 3],
+            1.,
+        );
+        assert!(buffers.positions.iter().all(|p| p[1] == 256.));
+        assert!(buffers.positions.contains(&[1., 256., 1.]));
+        buffers.clear();
+        assert!(buffers.positions.is_empty() && buffers.indices.is_empty());
+    }
+
+    /// Ensures that u16, strip and cached indices describe the same triangles as `indices`
+    #[test]
+    fn index_buffers() {
+        let n = 1000;
+        let list = bgm::indices(n);
+        assert_eq!(
+            bgm::indices_u16(n),
+            list.iter().map(|&i| i as u16).collect::<Vec<_>>()
+        );
+        // Splits the strip at the restart indices, odd triangles swap their first 2 vertices
+        let strip = bgm::strip_indices(n);
+        let mut triangles = Vec::new();
+        for quad in strip.split(|&i| i == u32::MAX) {
+            for t in 0..quad.len() - 2 {
+                let (a, b, c) = (quad[t], quad[t + 1], quad[t + 2]);
+                triangles.push(if t % 2 == 0 { [a, b, c] } else { [b, a, c] });
+            }
+        }
+        // The same triangles with the same winding, up to a rotation of their vertices
+        let rotated = |[a, b, c]: [u32; 3]| {
+            let min = a.min(b).min(c);
+            if a == min {
+                [a, b, c]
+            } else if b == min {
+                [b, c, a]
+            } else {
+                [c, a, b]
+            }
+        };
+        assert_eq!(
+            triangles.into_iter().map(rotated).collect::<Vec<_>>(),
+            list.chunks(3)
+                .map(|t| rotated([t[0], t[1], t[2]]))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            bgm::strip_indices_u16(n),
+            strip.iter().map(|&i| i as u16).collect::<Vec<_>>()
+        );
+
+        assert_eq!(bgm::Indices::new(n), bgm::Indices::U16(bgm::indices_u16(n)));
+        assert!(matches!(
+            bgm::Indices::new(bgm::U16_MAX_QUADS),
+            bgm::Indices::U16(_)
+        ));
+        assert!(matches!(
+            bgm::Indices::new(bgm::U16_MAX_QUADS + 1),
+            bgm::Indices::U32(_)
+        ));
+        assert!(matches!(
+            bgm::Indices::strip(bgm::U16_MAX_QUADS),
+            bgm::Indices::U32(_)
+        ));
+        assert_eq!(bgm::Indices::strip(n).len(), strip.len());
+
+        let mut cache = bgm::IndexCache::new();
+        assert_eq!(cache.get(n), bgm::IndexSlice::U16(&bgm::indices_u16(n)));
+        assert_eq!(cache.get_u32(10), &list[..60]);
+        assert_eq!(cache.get_u32(n), list);
+        assert_eq!(cache.get_u16(2 * n), bgm::indices_u16(2 * n));
+        assert_eq!(
+            cache.get(bgm::U16_MAX_QUADS + 1),
+            bgm::IndexSlice::U32(&bgm::indices(bgm::U16_MAX_QUADS + 1))
+        );
+    }
+
+    /// Ensures that the corner AO computed from the voxels matches the AO computed while meshing,
+    /// and that flipped quads keep their winding
+    #[test]
+    fn ao_diagonals() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh_ao(&voxels, &transparent_blocks);
+        let mut flipped = 0;
+        for (face, quads) in mesher.quads.iter().enumerate() {
+            let face = bgm::Face::from(face as u8);
+            for &quad in quads {
+                let ao = bgm::Mesher::<CS>::corner_ao(face, quad, &voxels, &transparent_blocks);
+                assert_eq!(ao, quad.ao());
+                flipped +=
+                    (bgm::quad_indices_ao(0, ao) != bgm::quad_indices_ao(0, [0; 4])) as usize;
+            }
+        }
+        assert!(flipped > 0);
+        let indices = bgm::Mesher::<CS>::ao_indices(&mesher.quads, &voxels, &transparent_blocks);
+        assert_eq!(
+            indices,
+            bgm::indices_ao(mesher.quads.iter().flatten().map(|quad| quad.ao()))
+        );
+
+        // Without AO, merged quads get the AO of their corners
+        let mut voxels = vec![0u16; bgm::Mesher::<CS>::CS_P3];
+        for x in 0..4 {
+            for z in 0..4 {
+                voxels[bgm::pad_linearize::<CS>(x, 0, z)] = 1;
+            }
+        }
+        // Occluding the corner 4;1;4 diagonally and the corner 0;1;4 from the side
+        voxels[bgm::pad_linearize::<CS>(4, 1, 4)] = 2;
+        voxels[bgm::pad_linearize::<CS>(0, 1, 4)] = 2;
+        mesher.clear();
+        mesher.mesh(&voxels, &bgm::TransparentSet::new());
+        let up = mesher.quads[bgm::Face::Up as usize]
+            .iter()
+            .find(|quad| quad.voxel_id() == 1)
+            .copied()
+            .unwrap();
+        assert_eq!((up.width(), up.height()), (4, 4));
+        let ao =
+            bgm::Mesher::<CS>::corner_ao(bgm::Face::Up, up, &voxels, &bgm::TransparentSet::new());
+        let corners = bgm::Face::Up.vertices_packed(up);
+        for (vertex, ao) in corners.iter().zip(ao) {
+            let expected = match vertex.xyz() {
+                [4, 1, 4] | [0, 1, 4] => 1,
+                _ => 0,
+            };
+            assert_eq!(ao, expected, "{:?}", vertex.xyz());
+        }
+
+        // Both diagonals give triangles facing the same way as the quad
+        let quad = bgm::Quad::<CS>::pack(3, 4, 5, 2, 3, 1);
+        for face in 0..6 {
+            let face = bgm::Face::from(face);
+            let positions = face
+                .vertices_packed(quad)
+                .map(|v| v.xyz().map(|c| c as i32));
+            let normal = |t: &[u32]| {
+                let [a, b, c] = [0, 1, 2].map(|i| positions[t[i] as usize]);
+                let (e1, e2) = (
+                    [0, 1, 2].map(|i| b[i] - a[i]),
+                    [0, 1, 2].map(|i| c[i] - a[i]),
+                );
+                [
+                    e1[1] * e2[2] - e1[2] * e2[1],
+                    e1[2] * e2[0] - e1[0] * e2[2],
+                    e1[0] * e2[1] - e1[1] * e2[0],
+                ]
+            };
+            let expected = normal(&bgm::quad_indices_ao(0, [0; 4])[..3]);
+            for t in bgm::quad_indices_ao(0, [0, 3, 3, 0]).chunks(3) {
+                let n = normal(t);
+                assert!((0..3).map(|i| n[i] * expected[i]).sum::<i32>() > 0);
+            }
+        }
+    }
+
+    /// Ensures that the OBJ output has every quad as 2 triangles, grouped by voxel id
+    #[cfg(feature = "obj")]
+    #[test]
+    fn obj_export() {
+        let voxels = test_buffer::<CS>();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        mesher.mesh(&voxels, &transparent_blocks);
+        let mut obj = Vec::new();
+        bgm::write_obj(&mut obj, &mesher.quads).unwrap();
+        let obj = alloc::string::String::from_utf8(obj).unwrap();
+
+        let num_quads: usize = mesher.quads.iter().map(Vec::len).sum();
+        let lines = |prefix: &'static str| obj.lines().filter(move |line| line.starts_with(prefix));
+        assert_eq!(lines("vn ").count(), 6);
+        assert_eq!(lines("v ").count(), 4 * num_quads);
+        assert_eq!(lines("f ").count(), 2 * num_quads);
+        assert_eq!(lines("o ").collect::<Vec<_>>(), ["o voxel_1", "o voxel_2"]);
+        assert_eq!(lines("usemtl ").count(), 2);
+        // Every face points to existing vertices and its face's normal
+        let positions: Vec<[u32; 3]> = lines("v ")
+            .map(|line| {
+                let mut coords = line[2..].split(' ').map(|c| c.parse().unwrap());
+                [0; 3].map(|_| coords.next().unwrap())
+            })
+            .collect();
+        for line in lines("f ") {
+            let corners: Vec<(usize, usize)> = line[2..]
+                .split(' ')
+                .map(|corner| {
+                    let (v, n) = corner.split_once("//").unwrap();
+                    (v.parse().unwrap(), n.parse().unwrap())
+                })
+                .collect();
+            assert_eq!(corners.len(), 3);
+            let face = bgm::Face::from(corners[0].1 as u8 - 1);
+            let axis = face.n().iter().position(|&d| d != 0).unwrap();
+            for &(v, n) in &corners {
+                assert_eq!(n, corners[0].1);
+                assert!((1..=positions.len()).contains(&v));
+                // The 3 vertices are on the face's plane
+                assert_eq!(positions[v - 1][axis], positions[corners[0].0 - 1][axis]);
+            }
+        }
+    }
+
+    /// Ensures that the OBJ vertices aren't limited by the 6 bits of the packed vertices on tall chunks
+    #[cfg(feature = "obj")]
+    #[test]
+    fn obj_export_tall_chunk() {
+        let voxels = chunk_buffer::<32, 256, 32>(|x, _y, z| (x == 0 && z == 0) as u16);
+        let mut mesher = bgm::ChunkMesher::<32, 256, 32>::new();
+        mesher.mesh(&voxels, &BTreeSet::new());
+        let mut obj = Vec::new();
+        bgm::write_obj(&mut obj, &mesher.quads).unwrap();
+        let obj = alloc::string::String::from_utf8(obj).unwrap();
+
+        let positions: Vec<[f32; 3]> = obj
+            .lines()
+            .filter(|line| line.starts_with("v "))
+            .map(|line| {
+                let mut coords = line[2..].split(' ').map(|c| c.parse().unwrap());
+                [0; 3].map(|_| coords.next().unwrap())
+            })
+            .collect();
+        let buffers = bgm::MeshBuffers::from_quads(&mesher.quads, [0.; 3], 1.);
+        assert_eq!(positions, buffers.positions);
+        assert!(positions.contains(&[0., 256., 0.]));
+    }
+
+    /// Ensures that a glTF reader finds the quads' vertices, indices and colors in the .glb buffer views
+    #[cfg(feature = "gltf")]
+    #[test]
+    fn glb_round_trip() {
+        let voxels = test_buffer::<CS>();
+        let light: Vec<u8> = (0..voxels.len()).map(|i| (i % 16) as u8).collect();
+        let transparent_blocks = bgm::TransparentSet::from([2]);
+        let mut mesher = bgm::Mesher::<CS>::new();
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            ..Default::default()
+        };
+        mesher.mesh_with(&voxels, &transparent_blocks, &attributes, &bgm::MergeSameId);
+        let colors = bgm::GltfColors {
+            ao: true,
+            light: true,
+        };
+        let mut glb = Vec::new();
+        bgm::write_glb(&mut glb, &mesher.quads, colors).unwrap();
+
+        let gltf = ::gltf::Gltf::from_slice(&glb).unwrap();
+        let blob = gltf.blob.as_deref().unwrap();
+        let groups = bgm::QuadGroups::new(&mesher.quads, |quad| quad.voxel_id());
+        let mesh = gltf.meshes().next().unwrap();
+        assert_eq!(mesh.primitives().len(), groups.groups.len());
+        for (primitive, group) in mesh.primitives().zip(&groups.groups) {
+            assert_eq!(
+                primitive.material().name(),
+                Some(alloc::format!("voxel_{}", group.key).as_str())
+            );
+            let mut buffers = bgm::MeshBuffers::new();
+            let mut expected_colors = Vec::new();
+            for (face, range) in group.faces.iter().enumerate() {
+                let face = bgm::Face::from(face as u8);
+                let quads = &groups.quads[range.clone()];
+                buffers.push_quads(face, quads, [0.; 3], 1.);
+                for &quad in quads {
+                    let light = quad.light().unwrap() as f32 / 15.;
+                    for ao in quad.ao() {
+                        expected_colors.push([light * (1. - ao as f32 / 4.); 3]);
+                    }
+                }
+            }
+            let reader = primitive.reader(|_| Some(blob));
+            assert_eq!(
+                reader.read_positions().unwrap().collect::<Vec<_>>(),
+                buffers.positions
+            );
+            assert_eq!(
+                reader.read_normals().unwrap().collect::<Vec<_>>(),
+                buffers.normals
+            );
+            assert_eq!(
+                reader
+                    .read_tex_coords(0)
+                    .unwrap()
+                    .into_f32()
+                    .collect::<Vec<_>>(),
+                buffers.uvs
+            );
+            assert_eq!(
+                reader
+                    .read_colors(0)
+                    .unwrap()
+                    .into_rgb_f32()
+                    .collect::<Vec<_>>(),
+                expected_colors
+            );
+            assert!(matches!(
+                reader.read_indices().unwrap(),
+                ::gltf::mesh::util::ReadIndices::U16(_)
+            ));
+            assert_eq!(
+                reader
+                    .read_indices()
+                    .unwrap()
+                    .into_u32()
+                    .collect::<Vec<_>>(),
+                buffers.indices
+            );
+            // Every buffer view is 4 bytes aligned inside the binary chunk
+            for view in gltf.views() {
+                assert_eq!(view.offset() % 4, 0);
+                assert!(view.offset() + view.length() <= blob.len());
+            }
+        }
+
+        // Without colors or quads
+        let mut glb = Vec::new();
+        bgm::write_glb(&mut glb, &mesher.quads, bgm::GltfColors::default()).unwrap();
+        let gltf = ::gltf::Gltf::from_slice(&glb).unwrap();
+        let primitive = gltf.meshes().next().unwrap().primitives().next().unwrap();
+        assert!(primitive.get(&::gltf::Semantic::Colors(0)).is_none());
+        mesher.clear();
+        let mut glb = Vec::new();
+        bgm::write_glb(&mut glb, &mesher.quads, colors).unwrap();
+        let gltf = ::gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(gltf.meshes().len(), 0);
+    }
+
+    /// Ensures that AO and light can be added to a quad in any order
+    #[test]
+    fn quad_attributes() {
+        let quad = bgm::Quad::<CS>::pack(1, 2, 3, 4, 5, 6);
+        let lit = bgm::Quad::<CS>::pack_ao(1, 2, 3, 4, 5, 6, [0, 1, 2, 3]).with_light(7);
+        assert_eq!(lit, quad.with_light(7).with_ao_bits(0b11_10_01_00));
+        assert_eq!((lit.xyz(), lit.width(), lit.height()), ([1, 2, 3], 4, 5));
+        assert_eq!(
+            (lit.ao(), lit.light(), lit.voxel_id()),
+            ([0, 1, 2, 3], Some(7), 6)
+        );
+        assert_eq!(
+            (quad.ao(), quad.light(), quad.voxel_id()),
+            ([0; 4], None, 6)
+        );
+    }
+
+    fn chunk_buffer<const CX: usize, const CY: usize, const CZ: usize>(
+        voxel: impl Fn(usize, usize, usize) -> u16,
+    ) -> Box<[u16]>
+    where
+        bgm::Size<CZ>: bgm::ChunkSize,
+    {
+        let mut voxels = vec![0; bgm::ChunkMesher::<CX, CY, CZ>::VOXELS_P].into_boxed_slice();
+        for x in 0..CX {
+            for y in 0..CY {
+                for z in 0..CZ {
+                    voxels[bgm::ChunkMesher::<CX, CY, CZ>::pad_linearize(x, y, z)] = voxel(x, y, z);
+                }
+            }
+        }
+        voxels
+    }
+
+    /// The masks, the quads of each meshing method and the smooth light of the quads
+    /// for a 8x12x6 chunk in the layout `L`
+    #[allow(clippy::type_complexity)]
+    fn layout_results<L: bgm::Layout>()
+    -> (Box<[u32]>, Vec<[Vec<bgm::Quad<8, 12, 6>>; 6]>, Vec<[u8; 4]>) {
+        type Mesher<L> = bgm::ChunkMesher<8, 12, 6, u32, L>;
+        let world = |x: usize, y: usize, z: usize| match (x * 7 + y * 3 + z) % 5 {
+            0 | 1 => 0u16,
+            v => v as u16,
+        };
+        let mut voxels = vec![0; Mesher::<L>::VOXELS_P];
+        let mut light = vec![0; Mesher::<L>::VOXELS_P];
+        for y in 0..14 {
+            for x in 0..10 {
+                for z in 0..8 {
+                    voxels[Mesher::<L>::padded_index(x, y, z)] = world(x, y, z);
+                    light[Mesher::<L>::padded_index(x, y, z)] = ((x + 2 * y + 3 * z) % 16) as u8;
+                }
+            }
+        }
+        let transparents = bgm::TransparentSet::from([2]);
+        let chunk = bgm::ChunkMasks::<u16, _, 8, 12, 6, u32, L>::from_voxels(voxels, &transparents);
+        let mut results = Vec::new();
+        let mut mesher = Mesher::<L>::new();
+        mesher.mesh(chunk.voxels(), &transparents);
+        results.push(mesher.quads.clone());
+        mesher.clear();
+        mesher.fast_mesh(chunk.voxels(), chunk.opaque_mask(), chunk.trans_mask());
+        results.push(mesher.quads.clone());
+        let attributes = bgm::FaceAttributes {
+            ao: true,
+            light: Some(&light),
+            smooth_light: Some(&light),
+        };
+        mesher.clear();
+        mesher.mesh_with(
+            chunk.voxels(),
+            &transparents,
+            &attributes,
+            &bgm::MergeSameId,
+        );
+        results.push(mesher.quads.clone());
+        mesher.clear();
+        mesher.fast_mesh_with(
+            chunk.voxels(),
+            chunk.opaque_mask(),
+            chunk.trans_mask(),
+            &attributes,
+            &bgm::MergeSameId,
+        );
+        results.push(mesher.quads.clone());
+        let smooth_light = mesher
+            .quads
+            .iter()
+            .enumerate()
+            .flat_map(|(face, quads)| {
+                let face = bgm::Face::from(face as u8);
+                let light = &light;
+                quads
+                    .iter()
+                    .map(move |&quad| face.vertices_smooth_light_in::<L, _, _, _>(quad, light).1)
+            })
+            .collect();
+
+        let mut center = vec![0; 8 * 12 * 6];
+        for y in 0..12 {
+            for x in 0..8 {
+                for z in 0..6 {
+                    center[Mesher::<L>::linearize(x, y, z)] = chunk.get(x, y, z);
+                }
+            }
+        }
+        let air = [0; 12 * 8];
+        mesher.clear();
+        mesher.mesh_unpadded(
+            &center,
+            [&air[..48], &air[..48], &air[..72], &air[..72], &air, &air],
+            &transparents,
+        );
+        results.push(mesher.quads.clone());
+        (chunk.opaque_mask().into(), results, smooth_light)
+    }
+
+    fn same_results_for<const CS: usize>()
+    where
+        bgm::Size<CS>: bgm::ChunkSize,
+    {
+        let voxels = test_buffer::<CS>();
         let transparent_blocks = BTreeSet::from([2]);
-        let opaque_mask = bgm::compute_opaque_mask::<CS>(voxels.as_slice(), &BTreeSet::new());
-        let trans_mask =
-            bgm::compute_transparent_mask::<CS>(voxels.as_slice(), &transparent_blocks);
+        let opaque_mask = bgm::compute_opaque_mask::<CS, _>(&voxels, &BTreeSet::new());
+        let trans_mask = bgm::compute_transparent_mask::<CS, _>(&voxels, &transparent_blocks);
         let mut mesher1 = bgm::Mesher::<CS>::new();
-        mesher1.mesh(voxels.as_slice(), &transparent_blocks);
+        mesher1.mesh(&voxels, &transparent_blocks);
         let mut mesher2 = bgm::Mesher::<CS>::new();
-        mesher2.fast_mesh(voxels.as_slice(), &opaque_mask, &trans_mask);
+        mesher2.fast_mesh(&voxels, &opaque_mask, &trans_mask);
         assert_eq!(mesher1.quads, mesher2.quads);
     }
 
-    fn test_buffer() -> Box<[u16; bgm::Mesher::<CS>::CS_P3]> {
-        let mut voxels = Box::new([0; bgm::Mesher::<CS>::CS_P3]);
+    fn test_buffer<const CS: usize>() -> Box<[u16]>
+    where
+        bgm::Size<CS>: bgm::ChunkSize,
+    {
+        let mut voxels = vec![0; (CS + 2).pow(3)].into_boxed_slice();
         for x in 0..CS {
             for y in 0..CS {
                 for z in 0..CS {
-                    voxels[bgm::pad_linearize::<CS>(x, y, z)] = transparent_sphere(x, y, z);
+                    voxels[bgm::pad_linearize::<CS>(x, y, z)] = transparent_sphere::<CS>(x, y, z);
                 }
             }
         }
         voxels
     }
 
-    fn transparent_sphere(x: usize, y: usize, z: usize) -> u16 {
+    fn transparent_sphere<const CS: usize>(x: usize, y: usize, z: usize) -> u16 {
+        let center = (CS / 2) as i32;
         if x == 8 {
             2
-        } else if (x as i32 - 31).pow(2) + (y as i32 - 31).pow(2) + (z as i32 - 31).pow(2)
-            < 16 as i32
+        } else if (x as i32 - center).pow(2)
+            + (y as i32 - center).pow(2)
+            + (z as i32 - center).pow(2)
+            < 16
         {
             1
         } else {
@@ -0,0 +1,103 @@
+use core::fmt::Debug;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Shl, Shr};
+
+/// An unsigned integer used as a column mask, 1 bit per voxel along a padded column.
+/// Implemented for u32, u64 and u128, the padded column length (CS + 2) must fit in `BITS`.
+pub trait Mask:
+    Copy
+    + Eq
+    + Debug
+    + BitAnd<Output = Self>
+    + BitAndAssign
+    + BitOr<Output = Self>
+    + BitOrAssign
+    + Not<Output = Self>
+    + Shl<usize, Output = Self>
+    + Shr<usize, Output = Self>
+{
+    const BITS: usize;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn trailing_zeros(self) -> usize;
+
+    fn from_bool(value: bool) -> Self;
+}
+
+macro_rules! impl_mask {
+    ($($t:ty),*) => {
+        $(
+            impl Mask for $t {
+                const BITS: usize = <$t>::BITS as usize;
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                #[inline]
+                fn trailing_zeros(self) -> usize {
+                    <$t>::trailing_zeros(self) as usize
+                }
+
+                #[inline]
+                fn from_bool(value: bool) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_mask!(u32, u64, u128);
+
+/// Type-level chunk size, used to pick the narrowest column mask for a given `CS`
+pub struct Size<const CS: usize>;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Maps a chunk size to its column mask type (the narrowest one, which keeps the mesher's buffers small,
+/// u32 masks aren't measurably faster to mesh than u64 ones):
+/// - CS <= 30: u32
+/// - CS <= 62: u64
+/// - CS <= 126: u128
+///
+/// This trait is sealed and only implemented for 1 <= CS <= 126,
+/// the padded column (CS_P = CS + 2 voxels) must fit in the mask,
+/// so any other chunk size is rejected at compile time:
+/// ```compile_fail
+/// let mesher = binary_greedy_meshing::Mesher::<127>::new();
+/// ```
+/// ```compile_fail
+/// let mesher = binary_greedy_meshing::Mesher::<0>::new();
+/// ```
+pub trait ChunkSize: sealed::Sealed {
+    type Mask: Mask;
+}
+
+/// The column mask type used for chunks of size `CS`
+pub type MaskOf<const CS: usize> = <Size<CS> as ChunkSize>::Mask;
+
+macro_rules! impl_chunk_size {
+    ($mask:ty; $($cs:literal)*) => {
+        $(
+            impl sealed::Sealed for Size<$cs> {}
+
+            impl ChunkSize for Size<$cs> {
+                type Mask = $mask;
+            }
+        )*
+    };
+}
+
+impl_chunk_size!(u32;
+    1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30
+);
+impl_chunk_size!(u64;
+    31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60
+    61 62
+);
+impl_chunk_size!(u128;
+    63 64 65 66 67 68 69 70 71 72 73 74 75 76 77 78 79 80 81 82 83 84 85 86 87 88 89 90 91 92
+    93 94 95 96 97 98 99 100 101 102 103 104 105 106 107 108 109 110 111 112 113 114 115 116
+    117 118 119 120 121 122 123 124 125 126
+);
@@ -0,0 +1,55 @@
+use crate::{Face, Quad, QuadGroups, quad_indices};
+use alloc::vec::Vec;
+use std::io::{self, Write};
+
+/// Writes quads (such as [`crate::ChunkMesher::quads`]) as a Wavefront OBJ mesh, to open a chunk in Blender or attach it to a bug report.
+///
+/// Vertices are the quads' corners in chunk space (the positions of [`crate::MeshBuffers`], for any chunk size),
+/// with 1 normal per face ([`Face::n`]).
+/// Quads are grouped by voxel id, each group being an object `voxel_<id>` using the material `voxel_<id>`
+/// (no material library is written, viewers give each material a default look):
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut voxels = vec![0u16; bgm::Mesher::<16>::CS_P3];
+/// voxels[bgm::pad_linearize::<16>(0, 0, 0)] = 1;
+/// let mut mesher = bgm::Mesher::<16>::new();
+/// mesher.mesh(&voxels, &bgm::TransparentSet::new());
+/// let mut obj = Vec::new();
+/// bgm::write_obj(&mut obj, &mesher.quads).unwrap();
+/// let obj = String::from_utf8(obj).unwrap();
+/// assert!(obj.contains("usemtl voxel_1"));
+/// assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 12);
+/// ```
+pub fn write_obj<const CX: usize, const CY: usize, const CZ: usize>(
+    mut writer: impl Write,
+    quads: &[Vec<Quad<CX, CY, CZ>>; 6],
+) -> io::Result<()> {
+    writeln!(writer, "# binary-greedy-meshing {CX}x{CY}x{CZ} chunk")?;
+    // The normal of face i is vn i+1
+    for face in 0..6 {
+        let [x, y, z] = Face::from(face).n();
+        writeln!(writer, "vn {x} {y} {z}")?;
+    }
+    let groups = QuadGroups::new(quads, |quad| quad.voxel_id());
+    // OBJ indices start at 1
+    let mut vertices = 1;
+    for group in groups.groups.iter() {
+        writeln!(writer, "o voxel_{}", group.key)?;
+        writeln!(writer, "usemtl voxel_{}", group.key)?;
+        for (face, range) in group.faces.iter().enumerate() {
+            let normal = face + 1;
+            let face = Face::from(face as u8);
+            for &quad in &groups.quads[range.clone()] {
+                for ([x, y, z], _) in face.quad_corners(quad) {
+                    writeln!(writer, "v {x} {y} {z}")?;
+                }
+                for triangle in quad_indices(0).chunks(3) {
+                    let [a, b, c] = [0, 1, 2].map(|i| vertices + triangle[i]);
+                    writeln!(writer, "f {a}//{normal} {b}//{normal} {c}//{normal}")?;
+                }
+                vertices += 4;
+            }
+        }
+    }
+    writer.flush()
+}
@@ -1,76 +1,260 @@
 use alloc::string::String;
 
-pub(crate) const MASK_6: u64 = 0b111111;
+/// Amount of bits used by a quad position field along an axis of size `cs`:
+/// enough to hold every value in 0..=cs, and never less than 5 bits.
+/// This gives 5 bits for CS <= 31, 6 bits for CS <= 63 and 7 bits for CS <= 127.
+pub(crate) const fn coord_bits(cs: usize) -> u32 {
+    let bits = usize::BITS - cs.leading_zeros();
+    if bits < 5 { 5 } else { bits }
+}
+
+/// Amount of bits used by a quad size field spanning axes up to `cs` long:
+/// sizes are never 0, so the field holds 1..=2^bits with 2^bits stored as 0,
+/// a 256 tall chunk only needs 8 bits for its quads' sizes
+const fn size_bits(cs: usize) -> u32 {
+    coord_bits(cs.saturating_sub(1))
+}
 
+const fn max(a: usize, b: usize) -> usize {
+    if a > b { a } else { b }
+}
+
+/// A quad outputted by a `ChunkMesher<CX, CY, CZ>` (`Quad<CS>` for a cubic `Mesher<CS>`),
+/// the bit width of its fields depends on the chunk dimensions (see [`Quad::X_BITS`]),
+/// the default CS=62 gives the layout described in the README.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct Quad(pub u64);
-
-impl Quad {
-    /// x: 6 bits
-    /// y: 6 bits
-    /// z: 6 bits 18
-    /// width (w): 6 bits
-    /// height (h): 6 bits
-    /// voxel id (v): 32 bits
+pub struct Quad<const CX: usize = 62, const CY: usize = CX, const CZ: usize = CX>(pub u64);
+
+impl<const CX: usize, const CY: usize, const CZ: usize> Quad<CX, CY, CZ> {
+    /// Bits of the x position, enough to hold 0..=CX (at least 5)
+    pub const X_BITS: u32 = coord_bits(CX);
+    /// Bits of the y position, enough to hold 0..=CY (at least 5)
+    pub const Y_BITS: u32 = coord_bits(CY);
+    /// Bits of the z position, enough to hold 0..=CZ (at least 5)
+    pub const Z_BITS: u32 = coord_bits(CZ);
+    /// Bits of the width, which spans x (up, down, front and back faces) or y (right and left faces)
+    pub const W_BITS: u32 = size_bits(max(CX, CY));
+    /// Bits of the height, which spans z (up, down, right and left faces) or y (front and back faces)
+    pub const H_BITS: u32 = size_bits(max(CY, CZ));
+    const Y_SHIFT: u32 = Self::X_BITS;
+    const Z_SHIFT: u32 = Self::Y_SHIFT + Self::Y_BITS;
+    const W_SHIFT: u32 = Self::Z_SHIFT + Self::Z_BITS;
+    const H_SHIFT: u32 = Self::W_SHIFT + Self::W_BITS;
+    const FLAGS_SHIFT: u32 = Self::H_SHIFT + Self::H_BITS;
+    /// Offset of the voxel id, which takes the bits left after the 5 fields and 2 flag bits
+    /// (quads with AO or light values store them here and move the id up, see [`Quad::pack_ao`] and [`Quad::with_light`])
+    pub const ID_SHIFT: u32 = Self::FLAGS_SHIFT + 2;
+    /// Bits of the voxel id in quads without AO or light, each of them takes 8 of these bits:
+    /// 37 for CS <= 31, 32 for CS <= 63, 27 for CS <= 127 and 25 for 32x256x32 chunks.
+    /// Packing a quad with an id that doesn't fit panics.
+    pub const ID_BITS: u32 = 64 - Self::ID_SHIFT;
+    const AO_FLAG: u64 = 1 << Self::FLAGS_SHIFT;
+    const LIGHT_FLAG: u64 = 1 << (Self::FLAGS_SHIFT + 1);
+
+    /// x: [`Quad::X_BITS`] bits
+    /// y: [`Quad::Y_BITS`] bits
+    /// z: [`Quad::Z_BITS`] bits
+    /// width (w): [`Quad::W_BITS`] bits, a width of 2^W_BITS is stored as 0
+    /// height (h): [`Quad::H_BITS`] bits, a height of 2^H_BITS is stored as 0
+    /// flags (f): 2 bits, the lowest one is set when the quad has AO values, the other when it has a light value
+    /// voxel id (v): [`Quad::ID_BITS`] bits, minus 8 bits for AO and 8 bits for light when they're present
+    ///
+    /// layout for CS <= 31 (5 bits per field):
+    /// 0bvvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vffh_hhhh_wwww_wzzz_zzyy_yyyx_xxxx
+    ///
+    /// layout for CS = 32 (sizes up to 32 fit 5 bits):
+    /// 0bvvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvff_hhhh_hwww_wwzz_zzzz_yyyy_yyxx_xxxx
     ///
-    /// ao (a): 2 bits
+    /// layout for CS <= 63 (6 bits per field):
+    /// 0bvvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_ffhh_hhhh_wwww_wwzz_zzzz_yyyy_yyxx_xxxx
     ///
-    /// layout:
-    /// 0bvvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvv_00hh_hhhh_wwww_wwzz_zzzz_yyyy_yyxx_xxxx
+    /// layout for CS <= 127 (7 bits per field, 64 has 6 bits sizes like 32):
+    /// 0bvvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vvvf_fhhh_hhhh_wwww_wwwz_zzzz_zzyy_yyyy_yxxx_xxxx
+    ///
+    /// layout for 32x256x32 chunks:
+    /// 0bvvvv_vvvv_vvvv_vvvv_vvvv_vvvv_vffh_hhhh_hhhw_wwww_wwwz_zzzz_zyyy_yyyy_yyxx_xxxx
     #[inline]
     pub fn pack(x: usize, y: usize, z: usize, w: usize, h: usize, v_type: usize) -> Self {
-        Quad(((v_type << 32) | (h << 24) | (w << 18) | (z << 12) | (y << 6) | x) as u64)
+        assert!(
+            (v_type as u64) < 1 << Self::ID_BITS,
+            "the voxel id {v_type} doesn't fit in the {} bits of the quad's id",
+            Self::ID_BITS
+        );
+        Quad(
+            ((v_type as u64) << Self::ID_SHIFT)
+                | (h as u64 & Self::field_mask(Self::H_BITS)) << Self::H_SHIFT
+                | (w as u64 & Self::field_mask(Self::W_BITS)) << Self::W_SHIFT
+                | (z as u64) << Self::Z_SHIFT
+                | (y as u64) << Self::Y_SHIFT
+                | x as u64,
+        )
+    }
+
+    #[inline]
+    const fn field_mask(bits: u32) -> u64 {
+        (1 << bits) - 1
+    }
+
+    #[inline]
+    fn field(&self, shift: u32, bits: u32) -> u64 {
+        (self.0 >> shift) & Self::field_mask(bits)
+    }
+
+    /// A size field, 0 standing for 2^bits
+    #[inline]
+    fn size_field(&self, shift: u32, bits: u32) -> u64 {
+        match self.field(shift, bits) {
+            0 => 1 << bits,
+            size => size,
+        }
+    }
+
+    /// Same as [`Quad::pack`] but also stores the ambient occlusion of the 4 vertices,
+    /// in the order of [`crate::Face::vertices_packed`], each from 0 (unoccluded) to 3 (fully occluded).
+    /// The AO takes the 8 bits after the flags and the voxel id is moved up by 8 bits:
+    /// 0bvvvv_..._vvvv_aaaa_aaaa_ffhh_hhhh_... (for B = 6)
+    ///
+    /// This leaves [`Quad::ID_BITS`] - 8 bits to the voxel id (24 bits for CS <= 63), panics if it doesn't fit:
+    /// ```should_panic
+    /// use binary_greedy_meshing as bgm;
+    /// let quad = bgm::Quad::<62>::pack_ao(0, 0, 0, 1, 1, 1 << 24, [0; 4]);
+    /// ```
+    #[inline]
+    pub fn pack_ao(
+        x: usize,
+        y: usize,
+        z: usize,
+        w: usize,
+        h: usize,
+        v_type: usize,
+        ao: [u8; 4],
+    ) -> Self {
+        let ao = ao
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, &a)| bits | (a & 0b11) << (2 * i));
+        Self::pack(x, y, z, w, h, v_type).with_ao_bits(ao)
+    }
+
+    /// Adds AO values packed as 4 * 2 bits to a quad without AO
+    #[inline]
+    pub(crate) fn with_ao_bits(self, ao: u8) -> Self {
+        self.insert_byte(Self::AO_FLAG, Self::ID_SHIFT, ao)
+    }
+
+    /// Stores a light value in a quad without one, it takes the 8 bits after the AO (if any)
+    /// and the voxel id is moved up by 8 bits:
+    /// 0bvvvv_..._vvvv_llll_llll_ffhh_hhhh_... (for B = 6, without AO)
+    ///
+    /// Panics if the voxel id doesn't fit in the bits left (see [`Quad::ID_BITS`]):
+    /// ```should_panic
+    /// use binary_greedy_meshing as bgm;
+    /// let quad = bgm::Quad::<62>::pack_ao(0, 0, 0, 1, 1, 100_000, [0; 4]).with_light(15);
+    /// ```
+    #[inline]
+    pub fn with_light(self, light: u8) -> Self {
+        self.insert_byte(Self::LIGHT_FLAG, self.light_shift(), light)
+    }
+
+    /// Sets `flag` and inserts `byte` at bit `at`, moving the bits above it up by 8
+    #[inline]
+    fn insert_byte(self, flag: u64, at: u32, byte: u8) -> Self {
+        debug_assert!(self.0 & flag == 0);
+        assert!(
+            self.0.leading_zeros() >= 8,
+            "the voxel id {} doesn't fit in the {} bits of the quad's id left by its AO and light",
+            self.voxel_id(),
+            Self::ID_BITS - 8 * (self.has_ao() as u32 + self.has_light() as u32 + 1)
+        );
+        let low = self.0 & ((1 << at) - 1);
+        Quad(low | flag | (byte as u64) << at | (self.0 >> at) << (at + 8))
+    }
+
+    #[inline]
+    fn light_shift(&self) -> u32 {
+        Self::ID_SHIFT + 8 * self.has_ao() as u32
     }
 
     #[inline]
     pub fn xyz(&self) -> [u64; 3] {
-        let x = (self.0) & MASK_6;
-        let y = (self.0 >> 6) & MASK_6;
-        let z = (self.0 >> 12) & MASK_6;
-        [x, y, z]
+        [
+            self.field(0, Self::X_BITS),
+            self.field(Self::Y_SHIFT, Self::Y_BITS),
+            self.field(Self::Z_SHIFT, Self::Z_BITS),
+        ]
     }
 
     #[inline]
     pub fn width(&self) -> u64 {
-        (self.0 >> 18) & MASK_6
+        self.size_field(Self::W_SHIFT, Self::W_BITS)
     }
 
     #[inline]
     pub fn height(&self) -> u64 {
-        (self.0 >> 24) & MASK_6
+        self.size_field(Self::H_SHIFT, Self::H_BITS)
     }
 
     #[inline]
     pub fn voxel_id(&self) -> u64 {
-        self.0 >> 32
+        self.0 >> (self.light_shift() + 8 * self.has_light() as u32)
+    }
+
+    #[inline]
+    pub fn has_ao(&self) -> bool {
+        self.0 & Self::AO_FLAG != 0
+    }
+
+    /// Ambient occlusion of the 4 vertices, in the order of [`crate::Face::vertices_packed`],
+    /// from 0 (unoccluded) to 3 (fully occluded), all 0 for quads meshed without AO
+    #[inline]
+    pub fn ao(&self) -> [u8; 4] {
+        let bits = if self.has_ao() {
+            (self.0 >> Self::ID_SHIFT) as u8
+        } else {
+            0
+        };
+        core::array::from_fn(|i| (bits >> (2 * i)) & 0b11)
+    }
+
+    #[inline]
+    pub fn has_light(&self) -> bool {
+        self.0 & Self::LIGHT_FLAG != 0
+    }
+
+    /// Light value of the air cell in front of the quad, if it was meshed with a light buffer
+    #[inline]
+    pub fn light(&self) -> Option<u8> {
+        self.has_light()
+            .then(|| (self.0 >> self.light_shift()) as u8)
     }
 
     /// Unpacks quad data and formats it as "{x};{y};{z} {w}x{h} v={v_type}" for debugging
     #[inline]
     pub fn debug_quad(&self) -> String {
-        let mut quad = self.0;
-        let x = quad & MASK_6;
-        quad >>= 6;
-        let y = quad & MASK_6;
-        quad >>= 6;
-        let z = quad & MASK_6;
-        quad >>= 6;
-        let w = quad & MASK_6;
-        quad >>= 6;
-        let h = quad & MASK_6;
-        quad >>= 8;
-        let v_type = quad;
-        format!("{x};{y};{z} {w}x{h} v={v_type}")
+        let [x, y, z] = self.xyz();
+        let w = self.width();
+        let h = self.height();
+        let v_type = self.voxel_id();
+        let mut res = format!("{x};{y};{z} {w}x{h} v={v_type}");
+        if self.has_ao() {
+            res += &format!(" ao={:?}", self.ao());
+        }
+        if let Some(light) = self.light() {
+            res += &format!(" light={light}");
+        }
+        res
     }
 }
 
-impl alloc::fmt::Debug for Quad {
+impl<const CX: usize, const CY: usize, const CZ: usize> alloc::fmt::Debug for Quad<CX, CY, CZ> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Quad")
             .field("position", &self.xyz())
             .field("width", &self.width())
             .field("height", &self.height())
             .field("voxel_id", &self.voxel_id())
+            .field("ao", &self.ao())
+            .field("light", &self.light())
             .finish()
     }
 }
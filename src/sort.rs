@@ -0,0 +1,87 @@
+use crate::{Face, Quad, quad_indices};
+use alloc::vec::Vec;
+
+/// Sorts quads back to front from a camera position, to blend transparent quads correctly:
+/// ```
+/// use binary_greedy_meshing as bgm;
+/// let mut voxels = vec![0u16; bgm::Mesher::<16>::CS_P3];
+/// voxels[bgm::pad_linearize::<16>(0, 0, 0)] = 2;
+/// voxels[bgm::pad_linearize::<16>(4, 0, 0)] = 2;
+/// let mut mesher = bgm::Mesher::<16>::new();
+/// mesher.split_transparent = true;
+/// mesher.mesh(&voxels, &bgm::TransparentSet::from([2]));
+/// let mut sorter = bgm::QuadSorter::new(&mesher.transparent_quads);
+/// let indices = sorter.sort([10., 2., 2.]);
+/// assert_eq!(indices.len(), 12 * 6);
+/// ```
+///
+/// Quads are numbered like in [`crate::indices`]: the quads of each face in face order,
+/// so the vertices of the quad `i` are 4*i..4*i+4.
+/// The sorter keeps the previous order, which is nearly sorted when the camera moves a bit,
+/// so re-sorting after a camera move is close to linear.
+#[derive(Debug, Clone)]
+pub struct QuadSorter {
+    /// The center of each quad in chunk space
+    centers: Vec<[f32; 3]>,
+    /// The squared distance of each quad to the camera
+    distances: Vec<f32>,
+    /// The quads from back to front
+    order: Vec<u32>,
+    /// The index buffer of `order`
+    indices: Vec<u32>,
+}
+
+impl QuadSorter {
+    /// Takes the quads of each face (such as [`crate::ChunkMesher::transparent_quads`])
+    pub fn new<const CX: usize, const CY: usize, const CZ: usize>(
+        quads: &[Vec<Quad<CX, CY, CZ>>; 6],
+    ) -> Self {
+        let centers: Vec<_> = quads
+            .iter()
+            .enumerate()
+            .flat_map(|(face, quads)| {
+                let face = Face::from(face as u8);
+                quads.iter().map(move |&quad| quad_center(face, quad))
+            })
+            .collect();
+        let len = centers.len();
+        Self {
+            centers,
+            distances: vec![0.; len],
+            order: (0..len as u32).collect(),
+            indices: Vec::with_capacity(len * 6),
+        }
+    }
+
+    /// Sorts the quads from the furthest to the closest to `camera` (in chunk space)
+    /// and returns the index buffer to draw them in that order
+    pub fn sort(&mut self, camera: [f32; 3]) -> &[u32] {
+        for (distance, center) in self.distances.iter_mut().zip(self.centers.iter()) {
+            *distance = (0..3)
+                .map(|i| (center[i] - camera[i]) * (center[i] - camera[i]))
+                .sum();
+        }
+        let distances = &self.distances;
+        // The sort is adaptive, so sorting the previous order is cheap if the camera moved a bit
+        self.order
+            .sort_by(|&a, &b| distances[b as usize].total_cmp(&distances[a as usize]));
+        self.indices.clear();
+        self.indices
+            .extend(self.order.iter().flat_map(|&i| quad_indices(i)));
+        &self.indices
+    }
+
+    /// The quads from back to front, as of the last sort
+    pub fn order(&self) -> &[u32] {
+        &self.order
+    }
+}
+
+/// The center of a quad, with the vertices of [`Face::vertices_packed`]
+fn quad_center<const CX: usize, const CY: usize, const CZ: usize>(
+    face: Face,
+    quad: Quad<CX, CY, CZ>,
+) -> [f32; 3] {
+    let (min, max) = face.quad_extents(quad);
+    core::array::from_fn(|i| (min[i] + max[i]) as f32 / 2.)
+}
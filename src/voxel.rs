@@ -0,0 +1,181 @@
+use alloc::{boxed::Box, collections::btree_set::BTreeSet};
+
+/// A voxel value stored in the padded voxel buffers given to the mesher.
+/// Implemented for u8, u16 and u32, where 0 is air and the value itself is the quad's voxel id.
+pub trait Voxel: Copy + Eq {
+    /// The value of empty voxels, used to fill new chunks
+    const AIR: Self;
+
+    /// Air voxels produce no faces and never hide their neighbours' faces
+    #[inline]
+    fn is_air(self) -> bool {
+        self == Self::AIR
+    }
+
+    /// The voxel id stored in the output quads (see [`crate::Quad::voxel_id`])
+    fn quad_id(self) -> u32;
+}
+
+macro_rules! impl_voxel {
+    ($($t:ty),*) => {
+        $(
+            impl Voxel for $t {
+                const AIR: Self = 0;
+
+                #[inline]
+                fn quad_id(self) -> u32 {
+                    self as u32
+                }
+            }
+        )*
+    };
+}
+
+impl_voxel!(u8, u16, u32);
+
+/// How a voxel interacts with its neighbours' faces
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum VoxelKind {
+    /// Has no faces and doesn't hide any
+    Air,
+    /// Hides every face next to it
+    Opaque,
+    /// Only hides faces of the same voxel value next to it
+    Transparent,
+}
+
+/// Tells the mesher which voxels are air, opaque or transparent.
+///
+/// Implemented by [`TransparentSet`], a flat bitset over the u16 space which is the fast default,
+/// and by `BTreeSet<V>` (listing the transparent values) for compatibility.
+/// In both cases air is decided by [`Voxel::is_air`].
+pub trait VoxelClassifier<V: Voxel> {
+    fn classify(&self, voxel: V) -> VoxelKind;
+}
+
+impl<V: Voxel, C: VoxelClassifier<V>> VoxelClassifier<V> for &C {
+    #[inline]
+    fn classify(&self, voxel: V) -> VoxelKind {
+        (*self).classify(voxel)
+    }
+}
+
+impl<V: Voxel + Ord> VoxelClassifier<V> for BTreeSet<V> {
+    #[inline]
+    fn classify(&self, voxel: V) -> VoxelKind {
+        if voxel.is_air() {
+            VoxelKind::Air
+        } else if self.contains(&voxel) {
+            VoxelKind::Transparent
+        } else {
+            VoxelKind::Opaque
+        }
+    }
+}
+
+/// A set of transparent voxel ids stored as 1 bit per u16 value (8 KiB),
+/// classifying a voxel is a single bit lookup.
+/// Voxels with a [`Voxel::quad_id`] above u16::MAX are always opaque.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransparentSet {
+    bits: Box<[u64]>,
+}
+
+impl TransparentSet {
+    const LEN: usize = (u16::MAX as usize + 1) / 64;
+
+    /// Creates an empty set, every non-air voxel is opaque
+    pub fn new() -> Self {
+        Self {
+            bits: vec![0; Self::LEN].into_boxed_slice(),
+        }
+    }
+
+    pub fn insert(&mut self, id: u16) {
+        self.bits[id as usize / 64] |= 1 << (id % 64);
+    }
+
+    pub fn remove(&mut self, id: u16) {
+        self.bits[id as usize / 64] &= !(1 << (id % 64));
+    }
+
+    #[inline]
+    pub fn contains(&self, id: u16) -> bool {
+        (self.bits[id as usize / 64] >> (id % 64)) & 1 != 0
+    }
+}
+
+impl Default for TransparentSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<u16> for TransparentSet {
+    fn from_iter<T: IntoIterator<Item = u16>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for id in iter {
+            set.insert(id);
+        }
+        set
+    }
+}
+
+impl<const N: usize> From<[u16; N]> for TransparentSet {
+    fn from(ids: [u16; N]) -> Self {
+        ids.into_iter().collect()
+    }
+}
+
+impl<V: Voxel> VoxelClassifier<V> for TransparentSet {
+    #[inline]
+    fn classify(&self, voxel: V) -> VoxelKind {
+        if voxel.is_air() {
+            VoxelKind::Air
+        } else if u16::try_from(voxel.quad_id()).is_ok_and(|id| self.contains(id)) {
+            VoxelKind::Transparent
+        } else {
+            VoxelKind::Opaque
+        }
+    }
+}
+
+/// Decides which faces the greedy meshing can merge: faces of the same orientation and attributes
+/// are merged if their voxels have the same merge key, voxels without a key never merge.
+///
+/// Implemented by [`MergeSameId`] (the default), [`NeverMerge`],
+/// and closures `Fn(V) -> Option<u32>`, for example to merge grass variants sharing a texture
+/// or to keep animated blocks as 1 quad per face.
+/// Merged quads keep the voxel id of one of their faces.
+pub trait MergePolicy<V: Voxel> {
+    fn merge_key(&self, voxel: V) -> Option<u32>;
+}
+
+/// Merges faces of voxels with the same id
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeSameId;
+
+impl<V: Voxel> MergePolicy<V> for MergeSameId {
+    #[inline]
+    fn merge_key(&self, voxel: V) -> Option<u32> {
+        Some(voxel.quad_id())
+    }
+}
+
+/// Never merges faces, outputs 1 quad per visible face
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NeverMerge;
+
+impl<V: Voxel> MergePolicy<V> for NeverMerge {
+    #[inline]
+    fn merge_key(&self, _voxel: V) -> Option<u32> {
+        None
+    }
+}
+
+impl<V: Voxel, F: Fn(V) -> Option<u32>> MergePolicy<V> for F {
+    #[inline]
+    fn merge_key(&self, voxel: V) -> Option<u32> {
+        self(voxel)
+    }
+}